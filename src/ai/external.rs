@@ -0,0 +1,180 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, RecvTimeoutError},
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::rules::{BChange, Position};
+
+/// How long the engine gets to answer `uci` and `isready`
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Extra time the engine gets on top of the move time before giving up on it
+const MOVE_GRACE_TIME: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A UCI engine running as a separate process
+pub struct ExternalEngine {
+    name: String,
+    options: Vec<String>,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl ExternalEngine {
+    /// Starts the engine at `path` and waits for it to be ready
+    pub fn start(path: &str) -> Result<Self, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("Could not start the engine {}: {}", path, err))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // read the output on its own thread so waiting for a line can time out
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            name: path.to_owned(),
+            options: Vec::new(),
+            child,
+            stdin,
+            lines,
+        };
+
+        engine.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = engine.next_line(deadline)?;
+            let mut tokens = line.split_whitespace();
+
+            match (tokens.next(), tokens.next()) {
+                (Some("id"), Some("name")) => {
+                    engine.name = tokens.collect::<Vec<_>>().join(" ");
+                }
+                (Some("option"), Some("name")) => {
+                    let name = tokens.take_while(|&token| token != "type");
+                    engine.options.push(name.collect::<Vec<_>>().join(" "));
+                }
+                (Some("uciok"), _) => break,
+                _ => (),
+            }
+        }
+
+        engine.wait_until_ready()?;
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options.iter().any(|option| option == name)
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.wait_until_ready()
+    }
+
+    /// Asks the engine for its move in the position. Returns `None` if `stop` was set
+    /// before it answered, or if it has no move to play
+    pub fn best_move(
+        &mut self,
+        position: &Position,
+        move_time: Duration,
+        stop: &AtomicBool,
+    ) -> Result<Option<BChange>, String> {
+        // throw away anything left over from a search that was given up on
+        while self.lines.try_recv().is_ok() {}
+
+        self.send(&position.to_uci_position())?;
+        self.send(&format!("go movetime {}", move_time.as_millis()))?;
+
+        let mut deadline = Instant::now() + move_time + MOVE_GRACE_TIME;
+        let mut stopped = false;
+
+        loop {
+            if !stopped && stop.load(Ordering::Relaxed) {
+                self.send("stop")?;
+                stopped = true;
+                deadline = Instant::now() + MOVE_GRACE_TIME;
+            }
+
+            let line = match self.lines.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) if Instant::now() < deadline => continue,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!("{} did not send its move in time", self.name))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("{} has stopped running", self.name))
+                }
+            };
+
+            let mut tokens = line.split_whitespace();
+            if tokens.next() != Some("bestmove") {
+                continue;
+            }
+
+            return match tokens.next() {
+                _ if stopped => Ok(None),
+                None | Some("0000") | Some("(none)") => Ok(None),
+                Some(text) => position
+                    .parse_uci_move(text)
+                    .map(Some)
+                    .map_err(|err| format!("{} played an illegal move: {}", self.name, err)),
+            };
+        }
+    }
+
+    fn wait_until_ready(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.next_line(deadline)? != "readyok" {}
+
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|err| format!("Could not talk to {}: {}", self.name, err))
+    }
+
+    fn next_line(&self, deadline: Instant) -> Result<String, String> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        self.lines.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => format!("{} did not answer in time", self.name),
+            RecvTimeoutError::Disconnected => format!("{} has stopped running", self.name),
+        })
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,268 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+use crate::rules::{BChange, BoardPos, Position};
+
+pub mod eval;
+pub mod external;
+
+use eval::{evaluate, piece_value};
+
+pub const MATE_SCORE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+
+/// How long a search is allowed to run for
+#[derive(Clone, Debug)]
+pub struct SearchLimits {
+    pub max_depth: u32,
+    pub time: Option<Duration>,
+    /// Set from another thread to stop the search early
+    pub stop: Arc<AtomicBool>,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 64,
+            time: None,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+/// How many moves until mate if the score is a forced mate, negative when
+/// the side to move is the one getting mated
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score.abs() < MATE_SCORE - 1000 {
+        return None;
+    }
+
+    let plies = MATE_SCORE - score.abs();
+    let moves = (plies + 1) / 2;
+    Some(score.signum() * moves)
+}
+
+/// The outcome of the deepest iteration that finished
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: BChange,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+struct Searcher<'a> {
+    limits: &'a SearchLimits,
+    deadline: Option<Instant>,
+    nodes: u64,
+    aborted: bool,
+}
+
+/// Searches the position with iterative deepening, returns `None` when there
+/// are no legal moves
+pub fn search(position: &Position, limits: &SearchLimits) -> Option<SearchResult> {
+    search_with_info(position, limits, |_| ())
+}
+
+/// Same as `search`, but calls `on_iteration` each time a depth is finished
+pub fn search_with_info(
+    position: &Position,
+    limits: &SearchLimits,
+    mut on_iteration: impl FnMut(&SearchResult),
+) -> Option<SearchResult> {
+    let mut position = position.clone();
+    let mut root_moves = position.get_all_possible_moves();
+    if root_moves.is_empty() {
+        return None;
+    }
+
+    let mut searcher = Searcher {
+        limits,
+        deadline: limits.time.map(|time| Instant::now() + time),
+        nodes: 0,
+        aborted: false,
+    };
+
+    order_moves(&position, &mut root_moves);
+    let mut result: Option<SearchResult> = None;
+
+    for depth in 1..=limits.max_depth.max(1) {
+        // the best move from the last iteration is searched first
+        if let Some(result) = &result {
+            if let Some(i) = root_moves.iter().position(|m| *m == result.best_move) {
+                let best = root_moves.remove(i);
+                root_moves.insert(0, best);
+            }
+        }
+
+        let mut alpha = -INFINITY;
+        let mut best_move = None;
+
+        for change in root_moves.iter() {
+            position.make_change(change.clone());
+            let score = -searcher.negamax(&mut position, depth - 1, 1, -INFINITY, -alpha);
+            position.unmake_change();
+
+            // the first iteration always finishes so there is a move to play
+            if searcher.aborted && depth > 1 {
+                break;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(change.clone());
+            }
+        }
+
+        if searcher.aborted && depth > 1 {
+            break;
+        }
+
+        let iteration = SearchResult {
+            best_move: best_move.unwrap_or_else(|| root_moves[0].clone()),
+            score: alpha,
+            depth,
+            nodes: searcher.nodes,
+        };
+        on_iteration(&iteration);
+        result = Some(iteration);
+
+        // no need to look deeper once a forced mate is found
+        if alpha.abs() >= MATE_SCORE - depth as i32 {
+            break;
+        }
+    }
+
+    result
+}
+
+impl Searcher<'_> {
+    fn should_stop(&mut self) -> bool {
+        if self.nodes.is_multiple_of(1024) {
+            let out_of_time = self.deadline.is_some_and(|d| Instant::now() >= d);
+            if out_of_time || self.limits.stop.load(Ordering::Relaxed) {
+                self.aborted = true;
+            }
+        }
+
+        self.aborted
+    }
+
+    fn negamax(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        // a repeated position is scored as a draw, so a winning side avoids repeating
+        if position.repetitions() > 1
+            || position.halfmove_clock() >= 100
+            || position.has_insufficient_material()
+        {
+            return 0;
+        }
+
+        let mut moves = position.get_all_possible_moves();
+        if moves.is_empty() {
+            return match position.is_in_check(position.turn()) {
+                true => -(MATE_SCORE - ply),
+                false => 0,
+            };
+        }
+
+        if depth == 0 {
+            return self.quiescence(position, alpha, beta);
+        }
+
+        order_moves(position, &mut moves);
+        for change in moves {
+            position.make_change(change);
+            let score = -self.negamax(position, depth - 1, ply + 1, -beta, -alpha);
+            position.unmake_change();
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    /// Keeps searching captures so the evaluation is not taken in the
+    /// middle of an exchange
+    fn quiescence(&mut self, position: &mut Position, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        let stand_pat = evaluate(position);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures: Vec<BChange> = position
+            .get_all_possible_moves()
+            .into_iter()
+            .filter(|change| change.is_capture(position))
+            .collect();
+        order_moves(position, &mut captures);
+
+        for change in captures {
+            position.make_change(change);
+            let score = -self.quiescence(position, -beta, -alpha);
+            position.unmake_change();
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+}
+
+/// Puts promotions and captures of valuable pieces by cheap ones first
+fn order_moves(position: &Position, moves: &mut [BChange]) {
+    moves.sort_by_cached_key(|change| -move_score(position, change));
+}
+
+fn move_score(position: &Position, change: &BChange) -> i32 {
+    let value_at = |square: BoardPos| {
+        position
+            .get_piece(square)
+            .map_or(0, |piece| piece_value(piece.piece_type))
+    };
+
+    match change {
+        BChange::MoveDestroy { start, target, .. } => 10 * value_at(*target) - value_at(*start),
+        BChange::Promotion {
+            end, piece_type, ..
+        } => piece_value(*piece_type) + 10 * value_at(*end),
+        BChange::PushPremote { piece_type, .. } => piece_value(*piece_type),
+        _ => 0,
+    }
+}
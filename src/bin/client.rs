@@ -0,0 +1,201 @@
+//! Plays a network game without a window. Messages typed on stdin are sent the way
+//! they are written on the wire, like `move e2 e4`, and everything that arrives is
+//! printed the same way, so games can be played from scripts and tests
+
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use chess::net::{Client, Host, Message, NetMove, NetOptions};
+use chess::rules::{Clock, Position, Side};
+
+/// How long to wait between looking for messages
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn main() {
+    let mut options = NetOptions::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let wanted = [
+            "--host",
+            "--join",
+            "--watch",
+            "--game",
+            "--resume",
+            "--variant",
+            "--time",
+            "--grace",
+        ];
+        match options.read(&wanted, &arg, &mut args) {
+            Ok(true) => (),
+            Ok(false) => {
+                println!("Unknown argument: {}", arg);
+                return;
+            }
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        }
+    }
+
+    let NetOptions {
+        host,
+        join,
+        watch,
+        game,
+        resume,
+        variant,
+        time_control,
+        grace,
+        ..
+    } = options;
+    let lines = read_stdin();
+    match (host, join) {
+        (Some(port), _) => match Host::bind(&format!("0.0.0.0:{}", port), Side::White) {
+            Ok(host) => {
+                let mut host = host.with_grace_period(grace);
+                if let Ok(address) = host.local_addr() {
+                    println!("Waiting for a player to join on {}", address);
+                }
+                let position = Position::new().with_variant(variant);
+                host.set_up(position, Clock::new(time_control));
+                run_host(host, lines);
+            }
+            Err(err) => println!("{}", err),
+        },
+        (None, Some(address)) => {
+            let hello = match (resume, watch) {
+                (Some((game, token)), _) => Message::Resume { game, token },
+                (None, true) => Message::Watch(game),
+                (None, false) => Message::Play,
+            };
+            match Client::connect(&address, &hello) {
+                Ok(client) => run_guest(client, lines),
+                Err(err) => println!("{}", err),
+            }
+        }
+        (None, None) => println!("--host or --join should be given"),
+    }
+}
+
+/// The lines typed on stdin, read on their own thread so waiting for them does not
+/// hold up the game
+fn read_stdin() -> Receiver<String> {
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    lines
+}
+
+/// Sends what is typed and prints what arrives, following the game to know when
+/// it is over and there is no seat to get back to
+fn run_guest(mut client: Client, lines: Receiver<String>) {
+    let mut position = Position::new();
+
+    loop {
+        for line in lines.try_iter().filter(|line| !line.trim().is_empty()) {
+            match Message::parse(&line) {
+                Ok(message) => {
+                    if let Err(err) = client.send(&message) {
+                        println!("{}", err);
+                    }
+                }
+                Err(err) => println!("{}", err),
+            }
+        }
+
+        let over = position.game_status().is_over();
+        while let Some(message) = client.poll(over) {
+            let message = match message {
+                Ok(message) => message,
+                Err(err) => {
+                    println!("{}", err);
+                    continue;
+                }
+            };
+
+            match &message {
+                Message::Welcome { variant, game, .. } => {
+                    match Position::from_uci_position(game, *variant) {
+                        Ok(game) => position = game,
+                        Err(err) => println!("{}", err),
+                    }
+                }
+                Message::Move(net_move) => match net_move.validate(&position, position.turn()) {
+                    Ok(change) => {
+                        position.apply_change(change);
+                    }
+                    Err(err) => println!("{}", err),
+                },
+                Message::Resign(side) => position.resign(*side),
+                Message::Timeout(side) => position.time_out(*side),
+                Message::Draw => {
+                    position.claim_draw();
+                }
+                _ => (),
+            }
+            println!("{}", message);
+        }
+
+        if !client.is_connected() && !client.is_reconnecting() {
+            return;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Keeps the game for white, with white's moves typed in, and prints every move
+/// played and how the game ended
+fn run_host(mut host: Host, lines: Receiver<String>) {
+    let side = host.side();
+    let mut printed = 0;
+    let mut printed_ending = None;
+
+    loop {
+        for line in lines.try_iter().filter(|line| !line.trim().is_empty()) {
+            let position = host.game().position();
+            let played = match Message::parse(&line) {
+                Ok(Message::Move(net_move)) => net_move.validate(position, side),
+                Ok(Message::Resign(resigned)) if resigned == side => {
+                    host.resign(side);
+                    continue;
+                }
+                Ok(message) => Err(format!("The host can not send: {}", message)),
+                Err(err) => Err(err),
+            };
+            match played {
+                Ok(change) => host.play(change),
+                Err(reason) => println!("{}", Message::Reject(reason)),
+            }
+        }
+
+        // the guest's moves and the clock are played out like on the server
+        host.step();
+
+        let position = host.game().position();
+        for record in position.history().iter().skip(printed) {
+            println!("{}", Message::Move(NetMove::from_change(&record.change)));
+        }
+        printed = position.history().len();
+        let ending = Message::ending(position);
+        if ending != printed_ending {
+            if let Some(ending) = &ending {
+                println!("{}", ending);
+            }
+            printed_ending = ending;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
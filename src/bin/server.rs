@@ -0,0 +1,47 @@
+//! Hosts network games without a window, so any number of players can be paired up
+//! and play at the same time
+
+use chess::net::{NetOptions, Server};
+
+fn main() {
+    let mut options = NetOptions::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let wanted = ["--port", "--variant", "--time", "--grace"];
+        match options.read(&wanted, &arg, &mut args) {
+            Ok(true) => (),
+            Ok(false) => {
+                println!("Unknown argument: {}", arg);
+                return;
+            }
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        }
+    }
+
+    let NetOptions {
+        port,
+        variant,
+        time_control,
+        grace,
+        ..
+    } = options;
+    let mut server = match Server::bind(&format!("0.0.0.0:{}", port), variant, time_control) {
+        Ok(server) => server.with_grace_period(grace),
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    // the port is only known here when it was picked by binding to port 0
+    let address = server.local_addr().unwrap_or_else(|_| port.to_string());
+    match time_control {
+        Some(control) => println!("Serving {:?} {} games on {}", variant, control, address),
+        None => println!("Serving untimed {:?} games on {}", variant, address),
+    }
+    server.run();
+}
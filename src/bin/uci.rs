@@ -0,0 +1,171 @@
+//! Plays the game over the UCI protocol, so the engine can be loaded into chess GUIs
+//! and played against other engines
+
+use std::io::{self, BufRead};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chess::ai::{mate_in, search_with_info, SearchLimits};
+use chess::rules::{Position, Side, Variant};
+
+/// Time kept back so the engine does not lose on time because of overhead
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+struct Engine {
+    position: Position,
+    variant: Variant,
+    search: Option<(JoinHandle<()>, Arc<AtomicBool>)>,
+}
+
+fn main() {
+    let mut engine = Engine {
+        position: Position::new().with_variant(Variant::Standard),
+        variant: Variant::Standard,
+        search: None,
+    };
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name Chess");
+                println!("id author atbarry");
+                println!("option name RookPush type check default false");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                engine.stop();
+                engine.position = Position::new().with_variant(engine.variant);
+            }
+            Some("setoption") => engine.set_option(&tokens.collect::<Vec<_>>()),
+            Some("position") => {
+                if let Err(err) = engine.set_position(&tokens.collect::<Vec<_>>()) {
+                    println!("info string {}", err);
+                }
+            }
+            Some("go") => engine.go(&tokens.collect::<Vec<_>>()),
+            Some("stop") => engine.stop(),
+            Some("quit") => {
+                engine.stop();
+                break;
+            }
+            Some(other) => println!("info string Unknown command: {}", other),
+            None => (),
+        }
+    }
+
+    // a GUI that closes stdin without quit still gets the move it asked for
+    engine.stop();
+}
+
+impl Engine {
+    fn set_option(&mut self, tokens: &[&str]) {
+        // setoption name <name> value <value>
+        match tokens {
+            ["name", "RookPush", "value", value] => {
+                self.variant = match *value {
+                    "true" => Variant::RookPush,
+                    _ => Variant::Standard,
+                };
+                self.position = self.position.clone().with_variant(self.variant);
+            }
+            _ => println!("info string Unknown option: {}", tokens.join(" ")),
+        }
+    }
+
+    fn set_position(&mut self, tokens: &[&str]) -> Result<(), String> {
+        self.position = Position::from_uci_position(&tokens.join(" "), self.variant)?;
+        Ok(())
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        self.stop();
+
+        let mut limits = SearchLimits::default();
+        let mut infinite = false;
+        let mut clock = (None, None);
+        let mut increment = (0, 0);
+        let mut moves_to_go = None;
+
+        let mut tokens = tokens.iter();
+        while let Some(&token) = tokens.next() {
+            let mut value = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+
+            match token {
+                "depth" => limits.max_depth = value().unwrap_or(1) as u32,
+                "movetime" => limits.time = value().map(Duration::from_millis),
+                "wtime" => clock.0 = value(),
+                "btime" => clock.1 = value(),
+                "winc" => increment.0 = value().unwrap_or(0),
+                "binc" => increment.1 = value().unwrap_or(0),
+                "movestogo" => moves_to_go = value(),
+                "infinite" => infinite = true,
+                _ => (),
+            }
+        }
+
+        // split the time left over the rest of the game
+        let (time_left, inc) = match self.position.turn() {
+            Side::White => (clock.0, increment.0),
+            Side::Black => (clock.1, increment.1),
+        };
+        if let (None, Some(time_left)) = (limits.time, time_left) {
+            let budget = time_left / moves_to_go.unwrap_or(30).max(1) + inc / 2;
+            let budget = Duration::from_millis(budget.min(time_left));
+            limits.time = Some(
+                budget
+                    .saturating_sub(MOVE_OVERHEAD)
+                    .max(Duration::from_millis(1)),
+            );
+        }
+
+        let stop = limits.stop.clone();
+        let position = self.position.clone();
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+            let result = search_with_info(&position, &limits, |result| {
+                let score = match mate_in(result.score) {
+                    Some(moves) => format!("mate {}", moves),
+                    None => format!("cp {}", result.score),
+                };
+                println!(
+                    "info depth {} score {} nodes {} time {} pv {}",
+                    result.depth,
+                    score,
+                    result.nodes,
+                    start.elapsed().as_millis(),
+                    result.best_move.to_uci()
+                );
+            });
+
+            // an infinite search only reports its move once it is told to stop
+            while infinite && !limits.stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            match result {
+                Some(result) => println!("bestmove {}", result.best_move.to_uci()),
+                None => println!("bestmove 0000"),
+            }
+        });
+
+        self.search = Some((handle, stop));
+    }
+
+    /// Stops the running search and waits for it to send its move
+    fn stop(&mut self) {
+        if let Some((handle, stop)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+}
@@ -3,81 +3,18 @@ use crate::{
     constants::{BOARD_HEIGHT, BOARD_WIDTH, TILE_SIZE},
 };
 use bevy::prelude::*;
+use chess::rules::{BChange, BoardPos, DrawReason, PieceDiff, Position, Side};
 
-pub mod logic;
 pub mod spawning;
 
+/// Draws a `Position`, keeping a sprite for every piece on the board
 pub struct Board {
-    board: Pieces,
+    position: Position,
+    pieces: Vec<Vec<Option<Entity>>>,
     tiles: Vec<Vec<Entity>>,
     spawner: PieceSpawner,
-    turn: Side,
-    turn_num: u32,
-    previous: Vec<Pieces>,
-}
-
-type Pieces = Vec<Vec<Option<Piece>>>;
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub struct BoardPos {
-    pub x: usize,
-    pub y: usize,
-}
-
-#[derive(Clone)]
-pub enum BChange {
-    Move {
-        start: BoardPos,
-        end: BoardPos,
-    },
-    MoveDestroy {
-        start: BoardPos,
-        end: BoardPos,
-        target: BoardPos,
-    },
-    BothMove {
-        start1: BoardPos,
-        start2: BoardPos,
-        end1: BoardPos,
-        end2: BoardPos,
-    },
-    Promotion {
-        start: BoardPos,
-        end: BoardPos,
-    },
-    PushPremote {
-        start1: BoardPos,
-        start2: BoardPos,
-        end1: BoardPos,
-        end2: BoardPos,
-    },
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum PieceType {
-    King,
-    Queen,
-    Rook,
-    Bishop,
-    Knight,
-    Pawn,
-}
-
-#[derive(Clone, Copy, Debug)]
-pub enum Side {
-    White,
-    Black,
-}
-
-#[derive(Clone, Debug)]
-pub struct Piece {
-    pub piece_type: PieceType,
-    pub side: Side,
-    pub entity: Entity,
-    pub board_pos: BoardPos,
-    pub distance_moved: u32,
-    pub num_moves: u32,
-    pub turn_last_moved: u32,
+    /// Black is drawn at the bottom
+    flipped: bool,
 }
 
 pub struct PieceSpawner {
@@ -96,260 +33,193 @@ pub struct PieceSpawner {
 }
 
 impl Board {
-    fn save(&mut self) {
-        self.previous.push(self.board.clone());
+    pub fn position(&self) -> &Position {
+        &self.position
     }
 
-    fn next_turn(&mut self) {
-        self.turn = match self.turn {
-            Side::White => Side::Black,
-            Side::Black => Side::White,
-        };
-
-        self.turn_num += 1;
+    pub fn apply_board_change(&mut self, commands: &mut Commands, board_change: BChange) {
+        let diffs = self.position.apply_change(board_change);
+        self.apply_diffs(commands, diffs);
     }
 
-    fn previous_turn(&mut self) {
-        self.turn = match self.turn {
-            Side::White => Side::Black,
-            Side::Black => Side::White,
-        };
-
-        if self.turn_num > 0 {
-            self.turn_num -= 1;
+    /// Takes back the last change by moving only the pieces it touched back to
+    /// where they were, returns false if there was nothing to undo
+    pub fn undo_last_change(&mut self, commands: &mut Commands) -> bool {
+        match self.position.undo() {
+            Some(diffs) => {
+                self.apply_diffs(commands, diffs);
+                true
+            }
+            None => false,
         }
     }
 
-    pub fn is_turn(&self, side: Side) -> bool {
-        side.is_friendly(&self.turn)
+    /// Plays the last undone change again, returns false if there was nothing to redo
+    pub fn redo_change(&mut self, commands: &mut Commands) -> bool {
+        match self.position.redo() {
+            Some(diffs) => {
+                self.apply_diffs(commands, diffs);
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn apply_board_change(&mut self, commands: &mut Commands, board_change: BChange) {
-        self.save();
+    fn apply_diffs(&mut self, commands: &mut Commands, diffs: Vec<PieceDiff>) {
+        for diff in diffs {
+            match diff {
+                PieceDiff::Moved { from, to } => {
+                    //* Important gets the entity and leaves a none value in its place
+                    let entity = self.take_entity(from).unwrap();
 
-        match board_change {
-            BChange::Move { start, end } => {
-                self.move_piece(commands, start, end);
-            }
-            BChange::MoveDestroy { start, end, target } => {
-                let enemy = self.take(target).unwrap().entity;
-                commands.entity(enemy).despawn();
-                self.move_piece(commands, start, end);
-            }
-            BChange::BothMove {
-                start1,
-                start2,
-                end1,
-                end2,
-            } => {
-                self.move_both_pieces(commands, start1, start2, end1, end2);
-            }
-            BChange::Promotion { start, end } => {
-                let mut piece = self.get_piece(start).unwrap();
-                self.promote_piece(commands, &mut piece, PieceType::Queen);
-                self.set_piece(Some(piece), start);
+                    commands.entity(entity).insert(Moveable::new(
+                        self.to_world_pos(from),
+                        self.to_world_pos(to),
+                    ));
 
-                if let Some(enemy) = self.take(end) {
-                    commands.entity(enemy.entity).despawn();
+                    self.pieces[to.x][to.y] = Some(entity);
+                }
+                PieceDiff::Removed { at } => {
+                    if let Some(entity) = self.take_entity(at) {
+                        commands.entity(entity).despawn();
+                    }
+                }
+                PieceDiff::Promoted {
+                    at,
+                    piece_type,
+                    side,
+                } => {
+                    // get rid of the old entity and spawn a new one with the new type
+                    if let Some(entity) = self.take_entity(at) {
+                        commands.entity(entity).despawn();
+                    }
+
+                    let world_pos = self.to_world_pos(at);
+                    let entity = self
+                        .spawner
+                        .spawn_piece(commands, piece_type, side, world_pos);
+                    self.pieces[at.x][at.y] = Some(entity);
+                }
+                PieceDiff::Added {
+                    at,
+                    piece_type,
+                    side,
+                } => {
+                    let world_pos = self.to_world_pos(at);
+                    let entity = self
+                        .spawner
+                        .spawn_piece(commands, piece_type, side, world_pos);
+                    self.pieces[at.x][at.y] = Some(entity);
                 }
-
-                self.move_piece(commands, start, end);
-            }
-            BChange::PushPremote {
-                start1,
-                start2,
-                end1,
-                end2,
-            } => {
-                let mut piece = self.get_piece(start2).unwrap();
-                self.promote_piece(commands, &mut piece, PieceType::Rook);
-                self.set_piece(Some(piece), start2);
-                self.move_both_pieces(commands, start1, start2, end1, end2)
             }
         }
-
-        // finally change the turn
-        self.next_turn();
     }
 
-    fn move_piece(&mut self, commands: &mut Commands, start: BoardPos, end: BoardPos) {
-        //* Important gets the piece and leaves a none value in its place
-        let mut piece = self.board[start.x][start.y].take().unwrap();
-
-        commands.entity(piece.entity).insert(Moveable::new(
-            start.self_to_world_pos(),
-            end.self_to_world_pos(),
-        ));
+    pub fn resign(&mut self, side: Side) {
+        self.position.resign(side);
+    }
 
-        piece.move_piece(end, self.turn_num);
-        self.set_piece(Some(piece), end);
+    pub fn time_out(&mut self, side: Side) {
+        self.position.time_out(side);
     }
 
-    fn move_both_pieces(
-        &mut self,
-        commands: &mut Commands,
-        start1: BoardPos,
-        start2: BoardPos,
-        end1: BoardPos,
-        end2: BoardPos,
-    ) {
-        let mut piece1 = self.take(start1).unwrap();
-        let mut piece2 = self.take(start2).unwrap();
-
-        commands.entity(piece1.entity).insert(Moveable::new(
-            start1.self_to_world_pos(),
-            end1.self_to_world_pos(),
-        ));
-
-        commands.entity(piece2.entity).insert(Moveable::new(
-            start2.self_to_world_pos(),
-            end2.self_to_world_pos(),
-        ));
-
-        piece1.move_piece(end1, self.turn_num);
-        piece2.move_piece(end2, self.turn_num);
-
-        // dbg!(&piece1);
-        // dbg!(&piece2);
-
-        self.set_piece(Some(piece1), end1);
-        self.set_piece(Some(piece2), end2);
+    /// Ends the game in a draw if one can be claimed
+    pub fn claim_draw(&mut self) -> Option<DrawReason> {
+        self.position.claim_draw()
     }
 
     pub fn get_tile_entity(&self, board_pos: BoardPos) -> Entity {
         self.tiles[board_pos.x][board_pos.y]
     }
 
-    pub fn get_piece(&self, board_pos: BoardPos) -> Option<Piece> {
-        self.board[board_pos.x][board_pos.y].clone()
+    pub fn is_flipped(&self) -> bool {
+        self.flipped
     }
 
-    pub fn set_piece(&mut self, piece: Option<Piece>, board_pos: BoardPos) {
-        self.board[board_pos.x][board_pos.y] = piece;
-    }
+    /// Turns the board around, sliding every piece over to where its square is now
+    pub fn set_flipped(&mut self, commands: &mut Commands, flipped: bool) {
+        if self.flipped == flipped {
+            return;
+        }
 
-    // pub fn is_empty(&self, board_pos: BoardPos) -> bool {
-    //     self.board[board_pos.x][board_pos.y].is_none()
-    // }
+        for x in 0..BOARD_WIDTH {
+            for y in 0..BOARD_HEIGHT {
+                let board_pos = BoardPos::new(x, y).unwrap();
+                let from = self.to_world_pos(board_pos);
+                let to = to_world_pos(orient(board_pos, flipped));
 
-    pub fn is_occupied(&self, board_pos: BoardPos) -> bool {
-        self.board[board_pos.x][board_pos.y].is_some()
-    }
+                commands
+                    .entity(self.tiles[x][y])
+                    .insert(Transform::from_translation(to.extend(0.0)));
 
-    pub fn is_occupied_and_friendly(&self, board_pos: BoardPos, other: Side) -> bool {
-        if let Some(piece) = self.get_piece(board_pos) {
-            if piece.side.is_friendly(&other) {
-                return true;
+                if let Some(entity) = self.pieces[x][y] {
+                    commands.entity(entity).insert(Moveable::new(from, to));
+                }
             }
         }
 
-        false
+        self.flipped = flipped;
     }
 
-    pub fn take(&mut self, board_pos: BoardPos) -> Option<Piece> {
-        self.board[board_pos.x][board_pos.y].take()
+    /// The middle of the square in the world, wherever it is drawn
+    pub fn to_world_pos(&self, board_pos: BoardPos) -> Vec2 {
+        to_world_pos(orient(board_pos, self.flipped))
     }
-}
-
-impl BoardPos {
-    pub fn new(x: usize, y: usize) -> Result<Self, String> {
-        if x >= BOARD_WIDTH || y >= BOARD_HEIGHT {
-            return Err(format!("BoardPos out of bounds: x: {}, y: {}", x, y));
-        }
 
-        Ok(Self { x, y })
+    /// The square drawn at a point in the world
+    pub fn world_to_board(&self, pos: Vec3) -> Option<BoardPos> {
+        world_to_board(pos).map(|board_pos| orient(board_pos, self.flipped))
     }
 
-    pub fn to_world_pos(x: usize, y: usize) -> Vec2 {
-        Vec2::new((x as f32 + 0.5) * TILE_SIZE, (y as f32 + 0.5) * TILE_SIZE)
+    pub fn get_piece_entity(&self, board_pos: BoardPos) -> Option<Entity> {
+        self.pieces[board_pos.x][board_pos.y]
     }
 
-    pub fn self_to_world_pos(&self) -> Vec2 {
-        Self::to_world_pos(self.x, self.y)
+    /// Where the piece with this entity is on the board
+    pub fn find_piece_entity(&self, entity: Entity) -> Option<BoardPos> {
+        (0..BOARD_WIDTH)
+            .flat_map(|x| (0..BOARD_HEIGHT).map(move |y| (x, y)))
+            .find(|&(x, y)| self.pieces[x][y] == Some(entity))
+            .and_then(|(x, y)| BoardPos::new(x, y).ok())
     }
 
-    pub fn world_to_board(pos: Vec3) -> Option<BoardPos> {
-        let x = pos.x / TILE_SIZE;
-        let y = pos.y / TILE_SIZE;
-
-        if x >= 0.0 && y >= 0.0 {
-            let (x, y) = (x as usize, y as usize);
-
-            if x < BOARD_WIDTH && y < BOARD_HEIGHT {
-                return Some(Self::new(x, y).unwrap());
-            }
-        }
-
-        None
+    fn take_entity(&mut self, board_pos: BoardPos) -> Option<Entity> {
+        self.pieces[board_pos.x][board_pos.y].take()
     }
 }
 
-impl Side {
-    pub fn is_friendly(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Side::White, Side::White) => true,
-            (Side::Black, Side::Black) => true,
-            _ => false,
-        }
+/// Where a square is drawn, turning the board around when it is flipped. Flipping
+/// twice gets the square back
+fn orient(board_pos: BoardPos, flipped: bool) -> BoardPos {
+    match flipped {
+        true => BoardPos::new(
+            BOARD_WIDTH - 1 - board_pos.x,
+            BOARD_HEIGHT - 1 - board_pos.y,
+        )
+        .unwrap(),
+        false => board_pos,
     }
+}
 
-    pub fn is_enemy(&self, other: &Self) -> bool {
-        !self.is_friendly(other)
-    }
+/// The middle of the square with white at the bottom
+fn to_world_pos(board_pos: BoardPos) -> Vec2 {
+    Vec2::new(
+        (board_pos.x as f32 + 0.5) * TILE_SIZE,
+        (board_pos.y as f32 + 0.5) * TILE_SIZE,
+    )
 }
 
-impl BChange {
-    pub fn click_pos_to_activate_change(&self) -> BoardPos {
-        match self {
-            BChange::Move { end, .. } => *end,
-            BChange::MoveDestroy { end, .. } => *end,
-            BChange::BothMove { start2, .. } => *start2,
-            BChange::Promotion { end, .. } => *end,
-            BChange::PushPremote { start2, .. } => *start2,
-        }
-    }
+fn world_to_board(pos: Vec3) -> Option<BoardPos> {
+    let x = pos.x / TILE_SIZE;
+    let y = pos.y / TILE_SIZE;
 
-    pub fn convert_to_promotion(&self) -> BChange {
-        match self {
-            BChange::Move { start, end } => BChange::Promotion {
-                start: *start,
-                end: *end,
-            },
-            BChange::MoveDestroy { start, end, .. } => BChange::Promotion {
-                start: *start,
-                end: *end,
-            },
-            BChange::BothMove {
-                start1,
-                end1,
-                start2,
-                end2,
-            } => BChange::PushPremote {
-                start1: *start1,
-                end1: *end1,
-                start2: *start2,
-                end2: *end2,
-            },
-            BChange::Promotion { .. } => self.clone(),
-            BChange::PushPremote { .. } => self.clone(),
-        }
-    }
-}
+    if x >= 0.0 && y >= 0.0 {
+        let (x, y) = (x as usize, y as usize);
 
-impl Piece {
-    pub fn move_piece(&mut self, target: BoardPos, turn_num: u32) {
-        self.distance_moved += {
-            let x = self.board_pos.x as isize - target.x as isize;
-            let y = self.board_pos.y as isize - target.y as isize;
-            (x.abs() + y.abs()) as u32
-        };
-
-        self.board_pos = target;
-        self.num_moves += 1;
-        self.turn_last_moved = turn_num;
+        if x < BOARD_WIDTH && y < BOARD_HEIGHT {
+            return Some(BoardPos::new(x, y).unwrap());
+        }
     }
 
-    pub fn turns_since_last_move(&self, turn_num: u32) -> u32 {
-        turn_num - self.turn_last_moved
-    }
+    None
 }
@@ -1,9 +1,14 @@
-use super::{Board, BoardPos, Piece, PieceSpawner, PieceType, Side};
-use crate::components::Tile;
+use super::{Board, PieceSpawner};
+use crate::components::{CoordinateLabel, Tile};
 use crate::constants::{
-    BOARD_HEIGHT, BOARD_WIDTH, DARK_TILE_COLOR, LIGHT_TILE_COLOR, PIECE_Z_LAYER, TILE_SIZE,
+    BOARD_HEIGHT, BOARD_WIDTH, DARK_TILE_COLOR, LABEL_FONT_SIZE, LABEL_Z_LAYER, LIGHT_TILE_COLOR,
+    PICKER_COLOR, PICKER_Z_LAYER, PIECE_Z_LAYER, TILE_SIZE,
 };
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    text::{HorizontalAlign, Text2dBundle, TextAlignment, VerticalAlign},
+};
+use chess::rules::{BoardPos, PieceType, Position, Side, Variant, PROMOTION_TYPES};
 
 impl PieceSpawner {
     fn get_image(&self, piece_type: PieceType, side: Side) -> Handle<Image> {
@@ -23,121 +28,114 @@ impl PieceSpawner {
         }
     }
 
-    fn spawn_piece(
+    pub fn spawn_piece(
         &self,
         commands: &mut Commands,
         piece_type: PieceType,
         side: Side,
-        board_pos: BoardPos,
-    ) -> Option<Piece> {
+        world_pos: Vec2,
+    ) -> Entity {
         let image = self.get_image(piece_type, side);
-        let world_pos = BoardPos::to_world_pos(board_pos.x, board_pos.y);
-
-        let entity = commands
-            .spawn_bundle(SpriteBundle {
-                texture: image,
-                transform: Transform::from_xyz(world_pos.x, world_pos.y, PIECE_Z_LAYER),
-                ..Default::default()
-            })
-            .id();
 
-        let piece = Piece {
-            piece_type,
-            side,
-            entity,
-            board_pos,
-            distance_moved: 0,
-            num_moves: 0,
-            turn_last_moved: 0,
-        };
-
-        Some(piece)
-    }
-
-    fn respawn_piece(&self, commands: &mut Commands, mut piece: Piece) -> Piece {
-        let image = self.get_image(piece.piece_type, piece.side);
-        let world_pos = piece.board_pos.self_to_world_pos();
-
-        let id = commands
+        commands
             .spawn_bundle(SpriteBundle {
                 texture: image,
                 transform: Transform::from_xyz(world_pos.x, world_pos.y, PIECE_Z_LAYER),
                 ..Default::default()
             })
-            .id();
-
-        piece.entity = id;
-        piece
+            .id()
     }
 }
+
 impl Board {
     pub fn new(spawner: PieceSpawner) -> Self {
         Self {
-            board: vec![vec![None; BOARD_WIDTH]; BOARD_HEIGHT],
+            position: Position::new(),
+            pieces: vec![vec![None; BOARD_HEIGHT]; BOARD_WIDTH],
             tiles: Vec::new(),
             spawner,
-            turn: Side::White,
-            turn_num: 0,
-            previous: Vec::new(),
+            flipped: false,
         }
     }
 
-    pub fn undo_last_change(&mut self, commands: &mut Commands) {
-        if let Some(board) = self.previous.pop() {
-            dbg!("Undoing last change");
-            self.clear_board(commands);
-            dbg!("Cleared board");
-            self.board = board;
-            self.respawn_pieces(commands);
-            dbg!("Respawned pieces");
-            self.previous_turn();
-        }
+    /// Switches the rules, only meant to be used before the game starts
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.position = self.position.clone().with_variant(variant);
     }
 
-    fn clear_board(&mut self, commands: &mut Commands) {
-        for row in self.board.iter_mut() {
-            for square in row.iter_mut() {
-                if let Some(piece) = square {
-                    commands.entity(piece.entity).despawn();
-                }
-                *square = None;
-            }
-        }
+    /// Jumps straight to another position without animating anything
+    pub fn set_position(&mut self, commands: &mut Commands, position: Position) {
+        self.clear_board(commands);
+        self.position = position;
+        self.spawn_pieces(commands);
     }
 
-    fn respawn_pieces(&mut self, commands: &mut Commands) {
-        for row in self.board.iter_mut() {
-            for square in row.iter_mut() {
-                if let Some(piece) = square {
-                    *piece = self.spawner.respawn_piece(commands, piece.clone());
-                }
+    /// Replaces the pieces on the board with the position described by the FEN
+    pub fn load_fen(&mut self, commands: &mut Commands, fen: &str) -> Result<(), String> {
+        let position = Position::from_fen(fen)?.with_variant(self.position.variant());
+        self.set_position(commands, position);
+
+        Ok(())
+    }
+
+    fn clear_board(&mut self, commands: &mut Commands) {
+        for square in self.pieces.iter_mut().flatten() {
+            if let Some(entity) = square.take() {
+                commands.entity(entity).despawn();
             }
         }
     }
 
     pub fn restart_game(&mut self, commands: &mut Commands) {
         self.clear_board(commands);
-        self.turn = Side::White;
-        self.turn_num = 0;
-        self.previous = Vec::new();
-        self.board = vec![vec![None; BOARD_WIDTH]; BOARD_HEIGHT];
+        self.position = Position::new().with_variant(self.position.variant());
         self.spawn_pieces(commands);
     }
 
-    pub fn promote_piece(
-        &mut self,
+    /// Spawns the pieces a pawn can be promoted to in a column going from the
+    /// promotion square towards the middle of the board
+    pub fn spawn_promotion_options(
+        &self,
         commands: &mut Commands,
-        piece: &mut Piece,
-        new_type: PieceType,
-    ) {
-        piece.piece_type = new_type;
-        // get rid of the old entity
-        commands.entity(piece.entity).despawn();
-        // spawn a new entity with the new type
-        *piece = self
-            .spawner
-            .spawn_piece(commands, new_type, piece.side, piece.board_pos)
-            .unwrap();
+        side: Side,
+        square: BoardPos,
+    ) -> Vec<(Entity, BoardPos, PieceType)> {
+        let mut options = Vec::new();
+
+        for (i, piece_type) in PROMOTION_TYPES.into_iter().enumerate() {
+            let y = if square.y == 0 {
+                square.y + i
+            } else {
+                square.y - i
+            };
+
+            let board_pos = BoardPos::new(square.x, y).unwrap();
+            let world_pos = self.to_world_pos(board_pos);
+            let image = self.spawner.get_image(piece_type, side);
+
+            let entity = commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform::from_xyz(world_pos.x, world_pos.y, PICKER_Z_LAYER),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                        color: PICKER_COLOR,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(SpriteBundle {
+                        texture: image,
+                        transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                        ..Default::default()
+                    });
+                })
+                .id();
+
+            options.push((entity, board_pos, piece_type));
+        }
+
+        options
     }
 
     pub fn spawn_tiles(&mut self, commands: &mut Commands) {
@@ -150,7 +148,7 @@ impl Board {
                     DARK_TILE_COLOR
                 };
 
-                let pos = BoardPos::to_world_pos(x, y);
+                let pos = self.to_world_pos(BoardPos::new(x, y).unwrap());
 
                 let tile = commands
                     .spawn_bundle(SpriteBundle {
@@ -173,139 +171,77 @@ impl Board {
         }
     }
 
-    pub fn spawn_pieces(&mut self, commands: &mut Commands) {
-        // Spawn pawns
-        for x in 0..BOARD_WIDTH {
-            self.board[x][1] = self.spawner.spawn_piece(
-                commands,
-                PieceType::Pawn,
-                Side::White,
-                BoardPos::new(x, 1).unwrap(),
-            );
+    /// Spawns the file letters along the bottom edge and the rank numbers along the
+    /// left edge, whichever way round the board is
+    pub fn spawn_coordinates(&self, commands: &mut Commands, font: Handle<Font>) {
+        let (bottom, left) = match self.flipped {
+            true => (BOARD_HEIGHT - 1, BOARD_WIDTH - 1),
+            false => (0, 0),
+        };
 
-            self.board[x][BOARD_HEIGHT - 2] = self.spawner.spawn_piece(
-                commands,
-                PieceType::Pawn,
-                Side::Black,
-                BoardPos::new(x, BOARD_HEIGHT - 2).unwrap(),
-            );
+        let mut labels = Vec::new();
+        for x in 0..BOARD_WIDTH {
+            let file = ((b'a' + x as u8) as char).to_string();
+            // in the bottom right corner of the square
+            let corner = Vec2::new(TILE_SIZE / 2.0 - 0.5, -TILE_SIZE / 2.0 + 0.5);
+            let alignment = TextAlignment {
+                vertical: VerticalAlign::Bottom,
+                horizontal: HorizontalAlign::Right,
+            };
+            labels.push((BoardPos::new(x, bottom).unwrap(), file, corner, alignment));
+        }
+        for y in 0..BOARD_HEIGHT {
+            // in the top left corner of the square
+            let corner = Vec2::new(-TILE_SIZE / 2.0 + 0.5, TILE_SIZE / 2.0 - 0.5);
+            let alignment = TextAlignment {
+                vertical: VerticalAlign::Top,
+                horizontal: HorizontalAlign::Left,
+            };
+            labels.push((
+                BoardPos::new(left, y).unwrap(),
+                (y + 1).to_string(),
+                corner,
+                alignment,
+            ));
         }
 
-        // Spawn rooks
-        self.board[0][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Rook,
-            Side::White,
-            BoardPos::new(0, 0).unwrap(),
-        );
-
-        self.board[BOARD_WIDTH - 1][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Rook,
-            Side::White,
-            BoardPos::new(BOARD_WIDTH - 1, 0).unwrap(),
-        );
-
-        self.board[0][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Rook,
-            Side::Black,
-            BoardPos::new(0, BOARD_HEIGHT - 1).unwrap(),
-        );
-
-        self.board[BOARD_WIDTH - 1][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Rook,
-            Side::Black,
-            BoardPos::new(BOARD_WIDTH - 1, BOARD_HEIGHT - 1).unwrap(),
-        );
-
-        // Spawn knights
-        self.board[1][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Knight,
-            Side::White,
-            BoardPos::new(1, 0).unwrap(),
-        );
-
-        self.board[BOARD_WIDTH - 2][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Knight,
-            Side::White,
-            BoardPos::new(BOARD_WIDTH - 2, 0).unwrap(),
-        );
-
-        self.board[1][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Knight,
-            Side::Black,
-            BoardPos::new(1, BOARD_HEIGHT - 1).unwrap(),
-        );
-
-        self.board[BOARD_WIDTH - 2][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Knight,
-            Side::Black,
-            BoardPos::new(BOARD_WIDTH - 2, BOARD_HEIGHT - 1).unwrap(),
-        );
-
-        // Spawn bishops
-        self.board[2][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Bishop,
-            Side::White,
-            BoardPos::new(2, 0).unwrap(),
-        );
-
-        self.board[BOARD_WIDTH - 3][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Bishop,
-            Side::White,
-            BoardPos::new(BOARD_WIDTH - 3, 0).unwrap(),
-        );
-
-        self.board[2][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Bishop,
-            Side::Black,
-            BoardPos::new(2, BOARD_HEIGHT - 1).unwrap(),
-        );
-
-        self.board[BOARD_WIDTH - 3][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Bishop,
-            Side::Black,
-            BoardPos::new(BOARD_WIDTH - 3, BOARD_HEIGHT - 1).unwrap(),
-        );
-
-        // Spawn queen
-        self.board[3][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Queen,
-            Side::White,
-            BoardPos::new(3, 0).unwrap(),
-        );
-
-        self.board[3][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::Queen,
-            Side::Black,
-            BoardPos::new(3, BOARD_HEIGHT - 1).unwrap(),
-        );
-
-        // Spawn king
-        self.board[4][0] = self.spawner.spawn_piece(
-            commands,
-            PieceType::King,
-            Side::White,
-            BoardPos::new(4, 0).unwrap(),
-        );
+        for (board_pos, value, corner, alignment) in labels {
+            // the same colour as the other squares, so it stands out on this one
+            let color = match (board_pos.x + board_pos.y) % 2 == 0 {
+                true => DARK_TILE_COLOR,
+                false => LIGHT_TILE_COLOR,
+            };
+            let pos = self.to_world_pos(board_pos) + corner;
+
+            commands
+                .spawn_bundle(Text2dBundle {
+                    text: Text {
+                        alignment,
+                        ..Text::from_section(
+                            value,
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: LABEL_FONT_SIZE,
+                                color,
+                            },
+                        )
+                    },
+                    transform: Transform::from_xyz(pos.x, pos.y, LABEL_Z_LAYER)
+                        .with_scale(Vec3::splat(0.5)),
+                    ..Default::default()
+                })
+                .insert(CoordinateLabel);
+        }
+    }
 
-        self.board[4][BOARD_HEIGHT - 1] = self.spawner.spawn_piece(
-            commands,
-            PieceType::King,
-            Side::Black,
-            BoardPos::new(4, BOARD_HEIGHT - 1).unwrap(),
-        );
+    /// Spawns a sprite for every piece in the position
+    pub fn spawn_pieces(&mut self, commands: &mut Commands) {
+        for piece in self.position.pieces() {
+            let world_pos = self.to_world_pos(piece.board_pos);
+            let entity =
+                self.spawner
+                    .spawn_piece(commands, piece.piece_type, piece.side, world_pos);
+            self.pieces[piece.board_pos.x][piece.board_pos.y] = Some(entity);
+        }
     }
 }
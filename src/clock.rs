@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use chess::rules::{Clock, Side};
+
+use crate::{
+    board::Board,
+    components::ClockText,
+    constants::{
+        CLOCK_PANEL_HEIGHT, HISTORY_PANEL_WIDTH, LOW_TIME_COLOR, PANEL_COLOR, RUNNING_CLOCK_COLOR,
+        TEXT_COLOR,
+    },
+    game_over::GameOver,
+    history::HistoryView,
+    network::Network,
+    resources::{Computer, PgnReplay, PromotionPicker},
+};
+
+/// Below this the time left is shown in tenths of a second
+const LOW_TIME: Duration = Duration::from_secs(10);
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_clocks)
+            .add_system(run_clock)
+            .add_system(show_clocks.after(run_clock));
+    }
+}
+
+fn spawn_clocks(mut commands: Commands, server: Res<AssetServer>) {
+    let font = server.load("fonts/DejaVuSans.ttf");
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(HISTORY_PANEL_WIDTH), Val::Px(CLOCK_PANEL_HEIGHT)),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            color: PANEL_COLOR.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            // black sits at the top, on the same side of the board as its pieces
+            for side in [Side::Black, Side::White] {
+                parent
+                    .spawn_bundle(TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 36.0,
+                            color: TEXT_COLOR,
+                        },
+                    ))
+                    .insert(ClockText(side));
+            }
+        });
+}
+
+/// Runs the time of the side to move while the game is being played, and ends
+/// the game when it runs out. In a network game the host's game does that
+fn run_clock(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut clock: ResMut<Clock>,
+    mut computer: ResMut<Computer>,
+    mut picker: ResMut<PromotionPicker>,
+    network: Res<Network>,
+    replay: Res<PgnReplay>,
+    mut view: ResMut<HistoryView>,
+    game_over: Res<GameOver>,
+    time: Res<Time>,
+) {
+    // nobody's time runs before the first move or while a saved game is replayed.
+    // Looking back at an earlier position does not stop it
+    let live = view.live_position(&board);
+    let playing =
+        !live.history().is_empty() && replay.moves.is_empty() && game_over.status.is_none();
+
+    if network.is_online() {
+        // the host's game keeps the time and says when it runs out, the clock here
+        // only shows it and stops when the game does
+        if !playing || !network.is_connected() {
+            clock.run(None);
+        }
+        clock.tick(time.delta());
+        return;
+    }
+
+    let turn = live.turn();
+    clock.run(playing.then_some(turn));
+    if let Some(side) = clock.tick(time.delta()) {
+        computer.stop_thinking();
+        picker.close(&mut commands);
+        view.back_to_game(&mut commands, &mut board);
+        board.time_out(side);
+        println!("{:?} ran out of time", side);
+    }
+}
+
+fn show_clocks(clock: Res<Clock>, mut q_text: Query<(&mut Text, &ClockText)>) {
+    for (mut text, ClockText(side)) in q_text.iter_mut() {
+        let section = &mut text.sections[0];
+        let time_left = clock.time_left(*side);
+
+        section.value = match time_left {
+            Some(time_left) => format!("{:?}  {}", side, format_time(time_left)),
+            None => format!("{:?}  -", side),
+        };
+        section.style.color = match time_left {
+            Some(time_left) if time_left < LOW_TIME => LOW_TIME_COLOR,
+            _ if clock.running() == Some(*side) => RUNNING_CLOCK_COLOR,
+            _ => TEXT_COLOR,
+        };
+    }
+}
+
+/// Formats the time like `4:05`, or `0:09.3` when it is nearly out
+fn format_time(time: Duration) -> String {
+    if time < LOW_TIME {
+        let tenths = time.as_millis() / 100;
+        return format!("0:{:02}.{}", tenths / 10, tenths % 10);
+    }
+
+    let secs = time.as_secs();
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
@@ -1,4 +1,7 @@
 use bevy::prelude::*;
+use chess::rules::{Side, TimeControl};
+
+use crate::settings::Orientation;
 
 use crate::constants::PIECE_MOVE_TIME;
 
@@ -14,6 +17,50 @@ pub struct Tile {
     pub normal_color: Color,
 }
 
+/// A file letter or rank number along the edge of the board
+#[derive(Component)]
+pub struct CoordinateLabel;
+
+/// The result screen shown when the game ends
+#[derive(Component)]
+pub struct GameOverScreen;
+
+/// The buttons on the result screen
+#[derive(Component, Clone, Copy)]
+pub enum GameOverButton {
+    NewGame,
+    ReviewGame,
+}
+
+/// The list of moves beside the board
+#[derive(Component)]
+pub struct HistoryPanel;
+
+/// The buttons in the history panel
+#[derive(Component, Clone, Copy)]
+pub enum HistoryButton {
+    /// Shows the position after this many moves
+    Move(usize),
+    BackToGame,
+}
+
+/// Shows the time one side has left
+#[derive(Component)]
+pub struct ClockText(pub Side);
+
+/// The settings menu, and the button that opens it
+#[derive(Component)]
+pub struct SettingsScreen;
+
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButton {
+    Open,
+    TimeControl(Option<TimeControl>),
+    Orientation(Orientation),
+    Coordinates,
+    Close,
+}
+
 impl Moveable {
     pub fn new(start_pos: Vec2, target_pos: Vec2) -> Self {
         // dbg!("Moving from {:?} to {:?}", start_pos, target_pos);
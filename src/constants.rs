@@ -1,13 +1,17 @@
-use std::time::Duration;
-
 use bevy::prelude::Color;
+pub use chess::rules::{BOARD_HEIGHT, BOARD_WIDTH};
 
-pub const BOARD_WIDTH: usize = 8;
-pub const BOARD_HEIGHT: usize = 8;
 pub const TILE_SIZE: f32 = 20.0;
 
 pub const PIECE_MOVE_TIME: f32 = 1.0;
 pub const PIECE_Z_LAYER: f32 = 10.0;
+/// Above the other pieces, but under the promotion picker
+pub const DRAG_Z_LAYER: f32 = 15.0;
+pub const PICKER_Z_LAYER: f32 = 20.0;
+/// Between the tiles and the pieces
+pub const LABEL_Z_LAYER: f32 = 1.0;
+/// Drawn at half size, so the letters stay as blocky as the piece sprites
+pub const LABEL_FONT_SIZE: f32 = 8.0;
 
 pub const MOVE_COLOR: Color = Color::rgb(0.5, 0.5, 1.0);
 pub const DESTROY_COLOR: Color = Color::rgb(0.48, 0.06, 1.0);
@@ -15,8 +19,26 @@ pub const SWAP_COLOR: Color = Color::rgb(0.1, 0.8, 0.9);
 pub const PROMOTE_COLOR: Color = Color::rgb(1.0, 0.5, 0.4);
 pub const PUSH_PREMOTE_COLOR: Color = Color::rgb(0.28, 1.0, 1.0);
 pub const SELECTED_COLOR: Color = Color::rgb(0.0, 0.1, 0.6);
+pub const PICKER_COLOR: Color = Color::rgb(0.95, 0.85, 0.55);
+/// Mixed into the squares the last move was played from and to
+pub const LAST_MOVE_COLOR: Color = Color::rgb(0.9, 0.8, 0.2);
+pub const CHECK_COLOR: Color = Color::rgb(0.9, 0.15, 0.15);
+
+pub const OVERLAY_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.7);
+pub const TEXT_COLOR: Color = Color::rgb(0.95, 0.95, 0.95);
+pub const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.35);
+pub const BUTTON_HOVER_COLOR: Color = Color::rgb(0.35, 0.35, 0.5);
 
 pub const LIGHT_TILE_COLOR: Color = Color::rgb(0.8, 0.8, 1.0);
 pub const DARK_TILE_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
 
-pub const SLEEP_DUR: Duration = Duration::from_millis(10);
+pub const WINDOW_HEIGHT: f32 = 1000.0;
+pub const HISTORY_PANEL_WIDTH: f32 = 300.0;
+/// How many lines of moves fit in the history panel
+pub const HISTORY_PANEL_ROWS: usize = 22;
+pub const CLOCK_PANEL_HEIGHT: f32 = 100.0;
+pub const SETTINGS_BUTTON_HEIGHT: f32 = 50.0;
+pub const PANEL_COLOR: Color = Color::rgb(0.12, 0.12, 0.16);
+pub const CURRENT_MOVE_COLOR: Color = Color::rgb(0.3, 0.3, 0.6);
+pub const RUNNING_CLOCK_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+pub const LOW_TIME_COLOR: Color = Color::rgb(1.0, 0.35, 0.3);
@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+use chess::rules::GameStatus;
+
+use crate::{
+    board::Board,
+    components::{GameOverButton, GameOverScreen},
+    constants::{BUTTON_COLOR, BUTTON_HOVER_COLOR, OVERLAY_COLOR, TEXT_COLOR},
+    history::HistoryView,
+    resources::NewGame,
+};
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameOver::default())
+            .add_system(update_game_over)
+            .add_system(show_game_over_screen.after(update_game_over))
+            .add_system(game_over_buttons)
+            .add_system(end_review);
+    }
+}
+
+/// How the game ended, and whether the player is looking back over it
+#[derive(Default)]
+pub struct GameOver {
+    pub status: Option<GameStatus>,
+    pub reviewing: bool,
+}
+
+fn update_game_over(board: Res<Board>, view: Res<HistoryView>, mut game_over: ResMut<GameOver>) {
+    // stepping through the game while reviewing it should not change the result
+    if !board.is_changed() || view.is_viewing() {
+        return;
+    }
+
+    let status = board.position().game_status();
+    let status = status.is_over().then_some(status);
+
+    if game_over.status != status {
+        game_over.status = status;
+    }
+}
+
+fn show_game_over_screen(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    game_over: Res<GameOver>,
+    q_screen: Query<Entity, With<GameOverScreen>>,
+) {
+    if !game_over.is_changed() {
+        return;
+    }
+
+    for entity in q_screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(status) = game_over.status else {
+        return;
+    };
+
+    let font = server.load("fonts/DejaVuSans.ttf");
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            margin: UiRect::all(Val::Px(8.0)),
+            ..Default::default()
+        })
+    };
+
+    // while reviewing only a hint is shown, so the board can be seen
+    if game_over.reviewing {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(0.0),
+                        left: Val::Px(0.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                color: OVERLAY_COLOR.into(),
+                ..Default::default()
+            })
+            .insert(GameOverScreen)
+            .with_children(|parent| {
+                parent.spawn_bundle(text(
+                    "Left and right arrows step through the game, Escape shows the result",
+                    20.0,
+                ));
+            });
+        return;
+    }
+
+    let (title, reason) = describe(status);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: OVERLAY_COLOR.into(),
+            ..Default::default()
+        })
+        .insert(GameOverScreen)
+        .with_children(|parent| {
+            parent.spawn_bundle(text(&title, 60.0));
+            parent.spawn_bundle(text(&reason, 30.0));
+
+            for (button, label) in [
+                (GameOverButton::NewGame, "New game"),
+                (GameOverButton::ReviewGame, "Review game"),
+            ] {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(220.0), Val::Px(60.0)),
+                            margin: UiRect::all(Val::Px(8.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: BUTTON_COLOR.into(),
+                        ..Default::default()
+                    })
+                    .insert(button)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(text(label, 28.0));
+                    });
+            }
+        });
+}
+
+/// The headline and the reason shown on the result screen
+fn describe(status: GameStatus) -> (String, String) {
+    match status {
+        GameStatus::Checkmate { winner } => (format!("{:?} wins", winner), "by checkmate".into()),
+        GameStatus::Resignation { winner } => {
+            (format!("{:?} wins", winner), "by resignation".into())
+        }
+        GameStatus::Timeout { winner } => (format!("{:?} wins", winner), "on time".into()),
+        GameStatus::Stalemate => ("Draw".into(), "by stalemate".into()),
+        GameStatus::Draw { reason } => ("Draw".into(), format!("by {}", reason)),
+        GameStatus::Ongoing | GameStatus::Check => ("".into(), "".into()),
+    }
+}
+
+fn game_over_buttons(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut view: ResMut<HistoryView>,
+    mut game_over: ResMut<GameOver>,
+    mut new_game: EventWriter<NewGame>,
+    mut q_button: Query<(&Interaction, &GameOverButton, &mut UiColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in q_button.iter_mut() {
+        match interaction {
+            Interaction::Clicked => match button {
+                GameOverButton::NewGame => new_game.send(NewGame),
+                GameOverButton::ReviewGame => {
+                    game_over.reviewing = true;
+                    let moves = board.position().history().len();
+                    view.show(&mut commands, &mut board, moves);
+                }
+            },
+            Interaction::Hovered => *color = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+/// The review is over once the board is back at the end of the game
+fn end_review(view: Res<HistoryView>, mut game_over: ResMut<GameOver>) {
+    if game_over.reviewing && !view.is_viewing() {
+        game_over.reviewing = false;
+    }
+}
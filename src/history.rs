@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use chess::rules::Position;
+
+use crate::{
+    board::Board,
+    components::{HistoryButton, HistoryPanel},
+    constants::{
+        BUTTON_COLOR, BUTTON_HOVER_COLOR, CLOCK_PANEL_HEIGHT, CURRENT_MOVE_COLOR,
+        HISTORY_PANEL_ROWS, HISTORY_PANEL_WIDTH, PANEL_COLOR, SETTINGS_BUTTON_HEIGHT, TEXT_COLOR,
+    },
+    resources::{HiglightedSquares, PromotionPicker, SelectedSquare},
+};
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HistoryView::default())
+            .add_system(history_buttons)
+            .add_system(step_through_history)
+            .add_system(
+                show_history_panel
+                    .after(history_buttons)
+                    .after(step_through_history),
+            );
+    }
+}
+
+/// An earlier position being looked at. Nothing can be played until the
+/// live position is back on the board
+#[derive(Default)]
+pub struct HistoryView {
+    /// The position the game is really at
+    live: Option<Position>,
+}
+
+impl HistoryView {
+    pub fn is_viewing(&self) -> bool {
+        self.live.is_some()
+    }
+
+    /// The position the game is really at, even while an earlier one is shown
+    pub fn live_position<'a>(&'a self, board: &'a Board) -> &'a Position {
+        self.live.as_ref().unwrap_or_else(|| board.position())
+    }
+
+    /// Shows the position after `moves` moves, by stepping the pieces there
+    pub fn show(&mut self, commands: &mut Commands, board: &mut Board, moves: usize) {
+        let live = self.live.get_or_insert_with(|| board.position().clone());
+        let moves = moves.min(live.history().len());
+
+        while board.position().history().len() > moves {
+            if !board.undo_last_change(commands) {
+                break;
+            }
+        }
+
+        while board.position().history().len() < moves {
+            if !board.redo_change(commands) {
+                break;
+            }
+        }
+    }
+
+    /// Puts the live position back on the board
+    pub fn back_to_game(&mut self, commands: &mut Commands, board: &mut Board) {
+        if let Some(position) = self.live.take() {
+            board.set_position(commands, position);
+        }
+    }
+
+    /// Forgets the live position when a new game replaces it
+    pub fn clear(&mut self) {
+        self.live = None;
+    }
+}
+
+fn show_history_panel(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    board: Res<Board>,
+    view: Res<HistoryView>,
+    q_panel: Query<Entity, With<HistoryPanel>>,
+) {
+    if !board.is_changed() && !view.is_changed() {
+        return;
+    }
+
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let live = view.live_position(&board);
+    let shown = board.position().history().len();
+    let pairs = live.move_pairs();
+
+    // keep the move being shown in the panel when the list is too long to fit
+    let current_row = pairs
+        .iter()
+        .position(|pair| {
+            pair.white.map(|i| i + 1) == Some(shown) || pair.black.map(|i| i + 1) == Some(shown)
+        })
+        .unwrap_or(0);
+    let first_row = (current_row + 1).saturating_sub(HISTORY_PANEL_ROWS);
+
+    let font = server.load("fonts/DejaVuSans.ttf");
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: TEXT_COLOR,
+            },
+        )
+    };
+    let button_style = |width: f32| Style {
+        size: Size::new(Val::Px(width), Val::Px(30.0)),
+        margin: UiRect::all(Val::Px(2.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..Default::default()
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                // between the clocks and the settings button
+                size: Size::new(Val::Px(HISTORY_PANEL_WIDTH), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(CLOCK_PANEL_HEIGHT),
+                    bottom: Val::Px(SETTINGS_BUTTON_HEIGHT),
+                    right: Val::Px(0.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            color: PANEL_COLOR.into(),
+            ..Default::default()
+        })
+        .insert(HistoryPanel)
+        .with_children(|parent| {
+            parent.spawn_bundle(text("Moves", 30.0));
+
+            for pair in pairs.iter().skip(first_row).take(HISTORY_PANEL_ROWS) {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: Color::NONE.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|row| {
+                        row.spawn_bundle(text(&format!("{}.", pair.number), 20.0).with_style(
+                            Style {
+                                size: Size::new(Val::Px(50.0), Val::Auto),
+                                ..Default::default()
+                            },
+                        ));
+
+                        for i in [pair.white, pair.black] {
+                            let Some(i) = i else {
+                                row.spawn_bundle(text("...", 20.0).with_style(button_style(100.0)));
+                                continue;
+                            };
+
+                            let color = match i + 1 == shown {
+                                true => CURRENT_MOVE_COLOR,
+                                false => BUTTON_COLOR,
+                            };
+                            row.spawn_bundle(ButtonBundle {
+                                style: button_style(100.0),
+                                color: color.into(),
+                                ..Default::default()
+                            })
+                            .insert(HistoryButton::Move(i + 1))
+                            .with_children(|button| {
+                                button.spawn_bundle(text(&live.history()[i].san, 20.0));
+                            });
+                        }
+                    });
+            }
+
+            if view.is_viewing() {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: button_style(200.0),
+                        color: BUTTON_COLOR.into(),
+                        ..Default::default()
+                    })
+                    .insert(HistoryButton::BackToGame)
+                    .with_children(|button| {
+                        button.spawn_bundle(text("Back to game", 20.0));
+                    });
+            }
+        });
+}
+
+fn history_buttons(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut view: ResMut<HistoryView>,
+    mut selected: ResMut<SelectedSquare>,
+    mut highlighted: ResMut<HiglightedSquares>,
+    mut picker: ResMut<PromotionPicker>,
+    mut q_button: Query<(&Interaction, &HistoryButton, &mut UiColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in q_button.iter_mut() {
+        match interaction {
+            Interaction::Clicked => {
+                match *button {
+                    HistoryButton::Move(moves) => view.show(&mut commands, &mut board, moves),
+                    HistoryButton::BackToGame => view.back_to_game(&mut commands, &mut board),
+                }
+
+                forget_selection(&mut commands, &mut selected, &mut highlighted, &mut picker);
+            }
+            Interaction::Hovered => *color = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => {
+                *color = match *button {
+                    HistoryButton::Move(moves) if moves == board.position().history().len() => {
+                        CURRENT_MOVE_COLOR.into()
+                    }
+                    _ => BUTTON_COLOR.into(),
+                }
+            }
+        }
+    }
+}
+
+fn step_through_history(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut view: ResMut<HistoryView>,
+    mut selected: ResMut<SelectedSquare>,
+    mut highlighted: ResMut<HiglightedSquares>,
+    mut picker: ResMut<PromotionPicker>,
+    input: Res<Input<KeyCode>>,
+) {
+    let shown = board.position().history().len();
+
+    // left steps back from the live position too, the other keys only work while viewing
+    if input.just_pressed(KeyCode::Left) && shown > 0 {
+        view.show(&mut commands, &mut board, shown - 1);
+    } else if !view.is_viewing() {
+        return;
+    } else if input.just_pressed(KeyCode::Right) {
+        view.show(&mut commands, &mut board, shown + 1);
+    } else if input.just_pressed(KeyCode::Escape) {
+        view.back_to_game(&mut commands, &mut board);
+    } else {
+        return;
+    }
+
+    forget_selection(&mut commands, &mut selected, &mut highlighted, &mut picker);
+}
+
+/// A move half way through being picked can not be played once another position
+/// is shown
+fn forget_selection(
+    commands: &mut Commands,
+    selected: &mut SelectedSquare,
+    highlighted: &mut HiglightedSquares,
+    picker: &mut PromotionPicker,
+) {
+    picker.close(commands);
+    selected.piece = None;
+    selected.tile = None;
+    selected.changed = true;
+    highlighted.squares.clear();
+}
@@ -1,4 +1,4 @@
-use crate::board::BoardPos;
+use crate::board::Board;
 use crate::resources::MouseInfo;
 use crate::systems::on_click;
 use bevy::{prelude::*, render::camera::RenderTarget};
@@ -12,6 +12,7 @@ impl Plugin for InputPlugin {
 }
 
 fn update_mouse(
+    board: Res<Board>,
     wnds: Res<Windows>,
     q_camera: Query<(&Camera, &GlobalTransform)>,
     mouse_button: Res<Input<MouseButton>>,
@@ -20,11 +21,12 @@ fn update_mouse(
     mouse.world_cords = mouse_to_world(wnds, q_camera);
 
     mouse.board_pos = match mouse.world_cords {
-        Some(pos) => BoardPos::world_to_board(pos),
+        Some(pos) => board.world_to_board(pos),
         None => None,
     };
 
     mouse.just_clicked = mouse_button.just_pressed(MouseButton::Left);
+    mouse.just_released = mouse_button.just_released(MouseButton::Left);
     // if mouse.just_clicked {
     //     dbg!("Just clicked the mouse at", mouse.board_pos);
     // }
@@ -50,7 +52,7 @@ fn mouse_to_world(
     // check if the cursor is inside the window and get its position
     if let Some(screen_pos) = wnd.cursor_position() {
         // get the size of the window
-        let window_size = Vec2::new(wnd.width() as f32, wnd.height() as f32);
+        let window_size = Vec2::new(wnd.width(), wnd.height());
 
         // convert screen position [0..resolution] to ndc [-1..1] (gpu coordinates)
         let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
@@ -64,8 +66,8 @@ fn mouse_to_world(
         // // reduce it to a 2D value
         // let world_pos: Vec2 = world_pos;
 
-        return Some(world_pos);
+        Some(world_pos)
     } else {
-        return None;
+        None
     }
 }
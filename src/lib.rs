@@ -0,0 +1,5 @@
+//! The rules of the game and the computer player without any Bevy types, so they can run
+//! without a window
+pub mod ai;
+pub mod net;
+pub mod rules;
@@ -1,3 +1,6 @@
+// Bevy systems take every resource they use as an argument
+#![allow(clippy::too_many_arguments)]
+
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::{
     prelude::*,
@@ -6,16 +9,27 @@ use bevy::{
 };
 use bevy_inspector_egui::WorldInspectorPlugin;
 use board::{Board, PieceSpawner};
+use chess::rules::pgn::PgnGame;
+use clock::ClockPlugin;
 use constants::*;
+use game_over::GameOverPlugin;
+use history::HistoryPlugin;
 use input::InputPlugin;
-use resources::ResourcesPlugin;
+use network::NetworkPlugin;
+use resources::{Computer, PgnReplay, ResourcesPlugin, StartOptions};
+use settings::SettingsPlugin;
 use systems::SystemsPlugin;
 
 mod board;
+mod clock;
 mod components;
 mod constants;
+mod game_over;
+mod history;
 mod input;
+mod network;
 mod resources;
+mod settings;
 mod systems;
 
 #[cfg(debug_assertions)]
@@ -23,7 +37,7 @@ fn main() {
     App::new()
         .add_plugin(SetupPlugin)
         .add_plugin(WorldInspectorPlugin::new())
-        .add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(FrameTimeDiagnosticsPlugin)
         .add_plugin(LogDiagnosticsPlugin::default())
         .run();
 }
@@ -39,8 +53,8 @@ impl Plugin for SetupPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ImageSettings::default_nearest())
             .insert_resource(WindowDescriptor {
-                width: 1000.0,
-                height: 1000.0,
+                width: WINDOW_HEIGHT + HISTORY_PANEL_WIDTH,
+                height: WINDOW_HEIGHT,
                 title: "Chess".to_owned(),
                 present_mode: PresentMode::Fifo,
                 ..Default::default()
@@ -49,6 +63,11 @@ impl Plugin for SetupPlugin {
             .add_plugin(ResourcesPlugin)
             .add_plugin(InputPlugin)
             .add_plugin(SystemsPlugin)
+            .add_plugin(GameOverPlugin)
+            .add_plugin(HistoryPlugin)
+            .add_plugin(ClockPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(NetworkPlugin)
             .add_startup_system(create_board)
             .add_startup_system(camera_setup);
     }
@@ -56,18 +75,16 @@ impl Plugin for SetupPlugin {
 
 fn camera_setup(mut commands: Commands) {
     let size = TILE_SIZE * (BOARD_WIDTH) as f32;
+    // leave room on the right for the history panel
+    let width = size * (WINDOW_HEIGHT + HISTORY_PANEL_WIDTH) / WINDOW_HEIGHT;
 
     let camera_bundle = Camera2dBundle {
-        transform: Transform::from_xyz(
-            BOARD_WIDTH as f32 * TILE_SIZE / 2.0,
-            BOARD_HEIGHT as f32 * TILE_SIZE / 2.0,
-            999.0,
-        ),
+        transform: Transform::from_xyz(width / 2.0, BOARD_HEIGHT as f32 * TILE_SIZE / 2.0, 999.0),
 
         projection: OrthographicProjection {
             far: 1000.0,
             scaling_mode: ScalingMode::Auto {
-                min_width: size,
+                min_width: width,
                 min_height: size,
             },
             ..Default::default()
@@ -78,7 +95,13 @@ fn camera_setup(mut commands: Commands) {
     commands.spawn_bundle(camera_bundle);
 }
 
-fn create_board(mut commands: Commands, server: Res<AssetServer>) {
+fn create_board(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    options: Res<StartOptions>,
+    computer: Res<Computer>,
+    mut replay: ResMut<PgnReplay>,
+) {
     // Load the sprites
     let spawner = PieceSpawner {
         white_king: server.load("pieces/white_king.png"),
@@ -96,8 +119,35 @@ fn create_board(mut commands: Commands, server: Res<AssetServer>) {
     };
 
     let mut board = Board::new(spawner);
+    board.set_variant(computer.variant);
     board.spawn_tiles(&mut commands);
-    board.spawn_pieces(&mut commands);
+
+    let mut fen = options.fen.clone();
+    if let Some(path) = &options.pgn {
+        let game = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|pgn| PgnGame::parse(&pgn));
+
+        match game {
+            Ok(game) => {
+                if let Some(tag_fen) = game.tag("FEN") {
+                    fen = Some(tag_fen.to_owned());
+                }
+                replay.moves = game.moves.into();
+            }
+            Err(err) => println!("Could not load PGN: {}", err),
+        }
+    }
+
+    match &fen {
+        Some(fen) => {
+            if let Err(err) = board.load_fen(&mut commands, fen) {
+                println!("Could not load FEN, starting a normal game: {}", err);
+                board.spawn_pieces(&mut commands);
+            }
+        }
+        None => board.spawn_pieces(&mut commands),
+    }
 
     commands.insert_resource(board);
-}
\ No newline at end of file
+}
@@ -0,0 +1,199 @@
+use std::sync::{
+    mpsc::{self, Receiver, TryRecvError},
+    Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{Connection, Message, RECONNECT_GRACE};
+use crate::rules::Side;
+
+/// How often a player who lost the connection tries to get back in, and how long
+/// each try may take
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A player or spectator in a game kept by someone else, a host or the server.
+/// A player who loses the connection keeps trying to get back into their seat
+/// until the host would have given up on them
+pub struct Client {
+    address: String,
+    /// Gone once the connection is lost
+    connection: Option<Connection>,
+    /// Not known until the host says hello, and never known for a spectator
+    side: Option<Side>,
+    /// The game and token to take the seat back with, once the host has sent them
+    seat: Option<(u32, String)>,
+    /// How long the host waits for a player who lost the connection, as it said
+    /// with the seat
+    grace: Duration,
+    /// When the connection was lost, while there is still time to get back in
+    lost: Option<Instant>,
+    /// When to try getting back in next
+    retry: Instant,
+    /// A try at getting back in, made on its own thread so polling never waits for it.
+    /// Behind a mutex so the client can be shared between threads
+    connecting: Option<Mutex<Receiver<Connection>>>,
+    /// A resume was sent and the host has not let us back in yet
+    resuming: bool,
+    retry_interval: Duration,
+}
+
+impl Client {
+    /// Connects to the host and sends `hello`, which asks to play, watch or take a
+    /// seat back
+    pub fn connect(address: &str, hello: &Message) -> Result<Self, String> {
+        let mut connection = Connection::connect(address)?;
+        connection.send(hello)?;
+
+        let seat = match hello {
+            Message::Resume { game, token } => Some((*game, token.clone())),
+            _ => None,
+        };
+        Ok(Self {
+            address: address.to_owned(),
+            connection: Some(connection),
+            side: None,
+            resuming: seat.is_some(),
+            seat,
+            // until the host says otherwise
+            grace: RECONNECT_GRACE,
+            lost: None,
+            retry: Instant::now(),
+            connecting: None,
+            retry_interval: RETRY_INTERVAL,
+        })
+    }
+
+    /// Changes how often to try getting back in after losing the connection
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// The side played here, once the host has said
+    pub fn side(&self) -> Option<Side> {
+        self.side
+    }
+
+    /// The game and token the host gave for taking the seat back
+    pub fn seat(&self) -> Option<&(u32, String)> {
+        self.seat.as_ref()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Whether the connection was lost and there is still time to get back in
+    pub fn is_reconnecting(&self) -> bool {
+        self.lost.is_some()
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        match &mut self.connection {
+            Some(connection) => connection.send(message),
+            None => Err(format!("Not connected to {}", self.address)),
+        }
+    }
+
+    /// The next message from the host. While the connection is lost this tries
+    /// to get back in now and then, unless the game is `over` and there is
+    /// nothing to come back to
+    pub fn poll(&mut self, over: bool) -> Option<Result<Message, String>> {
+        let Some(connection) = &mut self.connection else {
+            self.reconnect(over);
+            return None;
+        };
+
+        loop {
+            match connection.poll() {
+                Some(Ok(Message::Reject(reason))) if self.resuming => {
+                    println!("Could not get back into the game: {}", reason);
+                    self.seat = None;
+                    self.resuming = false;
+                    self.lost = None;
+                }
+                Some(Ok(message)) => {
+                    match &message {
+                        Message::Welcome { side, .. } => {
+                            self.side = *side;
+                            self.resuming = false;
+                            self.lost = None;
+                        }
+                        Message::Seat { game, token, grace } => {
+                            self.seat = Some((*game, token.clone()));
+                            self.grace = *grace;
+                        }
+                        _ => (),
+                    }
+                    return Some(Ok(message));
+                }
+                Some(Err(err)) => return Some(Err(err)),
+                None if connection.is_open() => return None,
+                None => break,
+            }
+        }
+
+        self.connection = None;
+        if self.seat.is_some() && !over {
+            println!("Lost the connection to the host, trying to get back in");
+            // a try that got in but was not let back yet does not buy more time
+            self.lost = self.lost.or(Some(Instant::now()));
+            self.retry = Instant::now();
+        } else {
+            println!("Lost the connection to the host");
+        }
+        None
+    }
+
+    fn reconnect(&mut self, over: bool) {
+        let (Some((game, token)), Some(lost)) = (self.seat.clone(), self.lost) else {
+            return;
+        };
+        if over || lost.elapsed() >= self.grace {
+            println!("Could not get back into the game");
+            self.lost = None;
+            self.connecting = None;
+            return;
+        }
+
+        if let Some(connecting) = &mut self.connecting {
+            let Ok(connecting) = connecting.get_mut() else {
+                return;
+            };
+            match connecting.try_recv() {
+                Ok(connection) => {
+                    println!("Connected to {} again", connection.peer());
+                    self.connection = Some(connection);
+                    self.resuming = true;
+                }
+                Err(TryRecvError::Empty) => return,
+                // the try failed, wait for the next one
+                Err(TryRecvError::Disconnected) => (),
+            }
+            self.connecting = None;
+            return;
+        }
+        if Instant::now() < self.retry {
+            return;
+        }
+
+        self.retry = Instant::now() + self.retry_interval;
+        let address = self.address.clone();
+        let (sender, connecting) = mpsc::channel();
+        thread::spawn(move || {
+            let Ok(mut connection) = Connection::connect_timeout(&address, CONNECT_TIMEOUT) else {
+                return;
+            };
+            if connection.send(&Message::Resume { game, token }).is_ok() {
+                let _ = sender.send(connection);
+            }
+        });
+        self.connecting = Some(Mutex::new(connecting));
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError, TrySendError},
+    Arc, Mutex,
+};
+use std::thread;
+use std::time::Duration;
+
+use super::Message;
+
+/// How many messages can wait to be sent, or to be read, before the other end is
+/// given up on
+const QUEUE_LENGTH: usize = 256;
+/// The longest line the other end can send, which fits a welcome to a long game
+const MAX_LINE: usize = 16 * 1024;
+/// How long writing one message may take before the other end is given up on
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One end of a game played over TCP. Lines are read and written on their own
+/// threads, so neither checking for a message nor sending one blocks the game
+pub struct Connection {
+    /// Behind a mutex so the connection can be shared between threads
+    lines: Mutex<Receiver<String>>,
+    /// Taken when the connection is dropped, which lets the writer finish
+    outgoing: Option<SyncSender<String>>,
+    peer: String,
+    open: Arc<AtomicBool>,
+}
+
+impl Connection {
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let stream = TcpStream::connect(address)
+            .map_err(|err| format!("Could not connect to {}: {}", address, err))?;
+        Self::new(stream)
+    }
+
+    /// Like `connect`, but gives up after `timeout` instead of when the system
+    /// does, so trying again does not hold up the game
+    pub fn connect_timeout(address: &str, timeout: Duration) -> Result<Self, String> {
+        let error = |err: std::io::Error| format!("Could not connect to {}: {}", address, err);
+        let mut result = Err(format!(
+            "Could not connect to {}: no address found",
+            address
+        ));
+
+        for socket in address.to_socket_addrs().map_err(error)? {
+            match TcpStream::connect_timeout(&socket, timeout) {
+                Ok(stream) => return Self::new(stream),
+                Err(err) => result = Err(error(err)),
+            }
+        }
+        result
+    }
+
+    pub fn new(stream: TcpStream) -> Result<Self, String> {
+        let peer = stream
+            .peer_addr()
+            .map(|address| address.to_string())
+            .unwrap_or_else(|_| "unknown".to_owned());
+        // moves are tiny, sending them straight away matters more than packing them
+        stream.set_nodelay(true).map_err(|err| err.to_string())?;
+        stream
+            .set_write_timeout(Some(WRITE_TIMEOUT))
+            .map_err(|err| err.to_string())?;
+        let reader = stream.try_clone().map_err(|err| err.to_string())?;
+
+        let open = Arc::new(AtomicBool::new(true));
+        let (sender, lines) = mpsc::sync_channel(QUEUE_LENGTH);
+        let reading = open.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let limit = (MAX_LINE + 1) as u64;
+                match reader.by_ref().take(limit).read_line(&mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => (),
+                }
+
+                // an endless line or more messages than are read is not a player
+                let flooding = line.len() > MAX_LINE
+                    || match sender.try_send(line.trim_end_matches(['\n', '\r']).to_owned()) {
+                        Ok(()) => false,
+                        Err(TrySendError::Full(_)) => true,
+                        Err(TrySendError::Disconnected(_)) => break,
+                    };
+                if flooding {
+                    reading.store(false, Ordering::Relaxed);
+                    let _ = reader.get_ref().shutdown(Shutdown::Both);
+                    break;
+                }
+            }
+        });
+
+        let (outgoing, queue) = mpsc::sync_channel::<String>(QUEUE_LENGTH);
+        let writing = open.clone();
+        thread::spawn(move || {
+            let mut stream = stream;
+            for line in queue {
+                if !writing.load(Ordering::Relaxed)
+                    || stream.write_all(format!("{}\n", line).as_bytes()).is_err()
+                {
+                    writing.store(false, Ordering::Relaxed);
+                    break;
+                }
+            }
+
+            // everything queued has been sent, this also stops the reading thread
+            let _ = stream.shutdown(Shutdown::Both);
+        });
+
+        Ok(Self {
+            lines: Mutex::new(lines),
+            outgoing: Some(outgoing),
+            peer,
+            open,
+        })
+    }
+
+    /// The address of the other end
+    pub fn peer(&self) -> &str {
+        &self.peer
+    }
+
+    /// False once the other end has gone or a message could not be sent
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Relaxed)
+    }
+
+    /// Queues the message to be sent. Another end too slow to take its messages
+    /// is given up on rather than holding up everyone else
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        let Some(outgoing) = self.outgoing.as_ref().filter(|_| self.is_open()) else {
+            return Err(format!("{} has disconnected", self.peer));
+        };
+
+        match outgoing.try_send(message.to_string()) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.close();
+                Err(format!("{} is not keeping up", self.peer))
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                self.close();
+                Err(format!("{} has disconnected", self.peer))
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.open.store(false, Ordering::Relaxed);
+        // the writer stops without sending the rest of the queue
+        self.outgoing = None;
+    }
+
+    /// The next message if one has arrived. Lines that are not messages come back
+    /// as errors without closing the connection
+    pub fn poll(&mut self) -> Option<Result<Message, String>> {
+        let lines = self.lines.get_mut().ok()?;
+        match lines.try_recv() {
+            Ok(line) => Some(Message::parse(&line)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.open.store(false, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Waits for the next message, for when there is nothing else to do
+    pub fn recv(&mut self, timeout: Duration) -> Result<Message, String> {
+        let lines = self.lines.get_mut().map_err(|err| err.to_string())?;
+        match lines.recv_timeout(timeout) {
+            Ok(line) => Message::parse(&line),
+            Err(RecvTimeoutError::Timeout) => Err(format!("{} did not answer in time", self.peer)),
+            Err(RecvTimeoutError::Disconnected) => {
+                self.open.store(false, Ordering::Relaxed);
+                Err(format!("{} has disconnected", self.peer))
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::mem;
+use std::time::Instant;
+
+use super::{table::moves_since, Connection, Event, Table};
+use crate::rules::{BChange, Clock, Position, Side, TimeControl};
+
+/// A game played over the network, by the rules and on the clock of whoever keeps
+/// it: the server for each of its games, or the player hosting one
+pub struct Game {
+    position: Position,
+    clock: Clock,
+    /// When the clock was last brought up to date
+    ticked: Instant,
+    table: Table,
+    /// What happened to the game since the last step
+    events: Vec<Event>,
+}
+
+impl Game {
+    pub fn new(table: Table, position: Position, clock: Clock) -> Self {
+        Self {
+            position,
+            clock,
+            ticked: Instant::now(),
+            table,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.table.id()
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.table.spectators()
+    }
+
+    /// Nobody is playing any more, and the game can not go on
+    pub fn is_done(&self) -> bool {
+        self.position.game_status().is_over() && self.table.is_empty()
+    }
+
+    /// Whether the player of `side` has lost the connection and still has time to
+    /// come back, which stops their clock
+    pub fn is_away(&self, side: Side) -> bool {
+        self.table.is_away(side)
+    }
+
+    /// Whether a new player can sit down to play `side`, and why not
+    pub fn can_seat(&self, side: Side) -> Result<(), String> {
+        self.table.can_seat(side)
+    }
+
+    /// Gives `side` to a new player
+    pub fn seat(&mut self, side: Side, connection: Connection) {
+        self.table
+            .seat(side, connection, &self.position, &self.clock);
+    }
+
+    /// The side of the seat the token is for
+    pub fn seat_of(&self, token: &str) -> Option<Side> {
+        self.table.seat_of(token)
+    }
+
+    /// Puts a player back in their seat
+    pub fn resume(&mut self, side: Side, connection: Connection) {
+        self.table
+            .resume(side, connection, &self.position, &self.clock);
+    }
+
+    pub fn watch(&mut self, connection: Connection) {
+        self.table.watch(connection, &self.position, &self.clock);
+    }
+
+    /// Starts the game over from `position`, everyone hears about it with the next step
+    pub fn set_up(&mut self, position: Position, clock: Clock) {
+        self.position = position;
+        self.clock = clock;
+        self.ticked = Instant::now();
+    }
+
+    /// Plays a move made where the game is kept, like the host's own
+    pub fn play(&mut self, change: BChange) {
+        // the flag could fall before the move is made
+        self.tick();
+        if self.position.game_status().is_over() {
+            return;
+        }
+
+        self.clock.press(self.position.turn());
+        self.position.apply_change(change);
+    }
+
+    pub fn resign(&mut self, side: Side) {
+        if !self.position.game_status().is_over() {
+            self.position.resign(side);
+        }
+    }
+
+    /// Brings the game up to `position`, when it is kept somewhere else too, like
+    /// on the host's board. Moves made there since are played on the clock, and a
+    /// game that changed some other way or a new time control starts over
+    pub fn catch_up(&mut self, position: &Position, control: Option<TimeControl>) {
+        let played = moves_since(
+            &self.position.to_uci_position(),
+            &position.to_uci_position(),
+        );
+        let Some(played) = played.filter(|_| control == self.clock.control()) else {
+            self.set_up(position.clone(), Clock::new(control));
+            return;
+        };
+
+        let history = position.history();
+        for record in history[history.len() - played..].iter() {
+            self.play(record.change.clone());
+        }
+
+        // a resignation, a draw, or a new game from where the last one ended. A flag
+        // that fell meanwhile is for the board to hear about with the next step
+        let flagged = self
+            .events
+            .iter()
+            .any(|event| matches!(event, Event::Timeout(_)));
+        if position.game_status() != self.position.game_status() && !flagged {
+            self.position = position.clone();
+        }
+    }
+
+    /// Runs the clock of the side to move once the first move has been played,
+    /// unless that player has lost the connection
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.ticked;
+        self.ticked = now;
+
+        let turn = self.position.turn();
+        let playing = !self.position.history().is_empty()
+            && !self.position.game_status().is_over()
+            && !self.table.is_away(turn);
+        self.clock.run(playing.then_some(turn));
+
+        if let Some(side) = self.clock.tick(elapsed) {
+            println!("{:?} ran out of time in game {}", side, self.id());
+            self.position.time_out(side);
+            self.events.push(Event::Timeout(side));
+        }
+    }
+
+    /// Runs the clock, plays what the players sent and tells everyone about it.
+    /// Returns what happened to the game since the last step, for whoever shows it
+    pub fn step(&mut self) -> Vec<Event> {
+        self.tick();
+
+        while let Some(event) = self.table.poll(&self.position) {
+            match &event {
+                Event::Move { side, change } => {
+                    self.clock.press(*side);
+                    self.position.apply_change(change.clone());
+                }
+                Event::Resign(side) | Event::Abandoned(side) => self.position.resign(*side),
+                Event::Timeout(_) => (),
+            }
+            self.events.push(event);
+        }
+
+        // the players' clocks start, stop and wait for someone who is away with this one
+        self.table.sync(&self.position, &self.clock);
+        mem::take(&mut self.events)
+    }
+}
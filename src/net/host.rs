@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use super::{Event, Game, Lobby, Request, Table};
+use crate::rules::{BChange, Clock, Position, Side, TimeControl};
+
+/// The host only has the one game, but resuming it works like on the server
+pub const HOST_GAME: u32 = 1;
+
+/// A game hosted by one of its players. One other player can join to play the
+/// other side, and anyone else to watch. The game is kept here like on the server,
+/// and whoever plays on the host shows it
+pub struct Host {
+    lobby: Lobby,
+    /// Boxed, so hosting takes no more room than joining
+    game: Box<Game>,
+    side: Side,
+}
+
+impl Host {
+    /// Listens on `address` for a guest to play against `side`
+    pub fn bind(address: &str, side: Side) -> Result<Self, String> {
+        Ok(Self {
+            lobby: Lobby::bind(address)?,
+            game: Box::new(Game::new(
+                Table::new(HOST_GAME),
+                Position::new(),
+                Clock::new(None),
+            )),
+            side,
+        })
+    }
+
+    /// Changes how long a guest who loses the connection has to come back before
+    /// they lose the game
+    pub fn with_grace_period(self, grace: Duration) -> Self {
+        let table = Table::new(HOST_GAME).with_grace_period(grace);
+        Self {
+            game: Box::new(Game::new(table, Position::new(), Clock::new(None))),
+            ..self
+        }
+    }
+
+    /// The address to join, with the port picked when binding to port 0
+    pub fn local_addr(&self) -> Result<String, String> {
+        self.lobby.local_addr()
+    }
+
+    /// The side played on the host
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Whether the guest plays `side` and has lost the connection, which stops
+    /// their clock until they come back or run out of time to
+    pub fn is_away(&self, side: Side) -> bool {
+        self.game.is_away(side)
+    }
+
+    /// Starts the game over from `position`
+    pub fn set_up(&mut self, position: Position, clock: Clock) {
+        self.game.set_up(position, clock);
+    }
+
+    /// Plays a move made on the host
+    pub fn play(&mut self, change: BChange) {
+        self.game.play(change);
+    }
+
+    pub fn resign(&mut self, side: Side) {
+        self.game.resign(side);
+    }
+
+    /// Brings the game up to the one on the host's board, see `Game::catch_up`
+    pub fn catch_up(&mut self, position: &Position, control: Option<TimeControl>) {
+        self.game.catch_up(position, control);
+    }
+
+    /// Lets new connections play, come back or watch, then plays the game on. See
+    /// `Game::step`
+    pub fn step(&mut self) -> Vec<Event> {
+        let guest = self.side.opposite();
+
+        for (newcomer, request) in self.lobby.requests() {
+            match request {
+                Request::Play => match self.game.can_seat(guest) {
+                    Ok(()) => {
+                        println!("{} joined as {:?}", newcomer.peer(), guest);
+                        self.game.seat(guest, newcomer.into_connection());
+                    }
+                    Err(reason) => self.lobby.refuse(newcomer, &reason),
+                },
+                Request::Resume { game, token } => match self.game.seat_of(&token) {
+                    Some(side) if game == HOST_GAME => {
+                        self.game.resume(side, newcomer.into_connection())
+                    }
+                    _ => self
+                        .lobby
+                        .refuse(newcomer, "That is not a seat in this game"),
+                },
+                Request::Watch(_) => self.game.watch(newcomer.into_connection()),
+            }
+        }
+
+        self.game.step()
+    }
+}
@@ -0,0 +1,144 @@
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use super::{Connection, Message};
+
+/// How many bad messages a new connection may send before it is dropped
+const MAX_BAD_MESSAGES: u32 = 3;
+/// How long a new connection has to say what it wants
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Takes new connections and waits for them to ask for something, for both the
+/// server and a player hosting a game. Connections that never ask for anything
+/// sensible are dropped
+pub struct Lobby {
+    listener: TcpListener,
+    newcomers: Vec<Newcomer>,
+}
+
+/// A connection that has not joined a game yet
+pub struct Newcomer {
+    connection: Connection,
+    arrived: Instant,
+    bad_messages: u32,
+}
+
+/// What a new connection can ask for
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Request {
+    Play,
+    Watch(Option<u32>),
+    Resume { game: u32, token: String },
+}
+
+impl Lobby {
+    pub fn bind(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address)
+            .map_err(|err| format!("Could not listen on {}: {}", address, err))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            listener,
+            newcomers: Vec::new(),
+        })
+    }
+
+    /// The address to connect to, with the port picked when binding to port 0
+    pub fn local_addr(&self) -> Result<String, String> {
+        self.listener
+            .local_addr()
+            .map(|address| address.to_string())
+            .map_err(|err| err.to_string())
+    }
+
+    /// Accepts new connections and returns the ones that have asked for something.
+    /// Each of them has to be answered, or handed back with `refuse`
+    pub fn requests(&mut self) -> Vec<(Newcomer, Request)> {
+        self.accept();
+
+        let mut requests = Vec::new();
+        for mut newcomer in std::mem::take(&mut self.newcomers) {
+            let request = match newcomer.connection.poll() {
+                Some(Ok(Message::Play)) => Request::Play,
+                Some(Ok(Message::Watch(id))) => Request::Watch(id),
+                Some(Ok(Message::Resume { game, token })) => Request::Resume { game, token },
+                Some(Ok(message)) => {
+                    let reason = format!("Send play, watch or resume before {}", message);
+                    self.refuse(newcomer, &reason);
+                    continue;
+                }
+                Some(Err(err)) => {
+                    self.refuse(newcomer, &err);
+                    continue;
+                }
+                None if !newcomer.connection.is_open() => {
+                    println!("{} disconnected", newcomer.peer());
+                    continue;
+                }
+                None if newcomer.arrived.elapsed() >= HANDSHAKE_TIMEOUT => {
+                    println!("{} did not ask for anything in time", newcomer.peer());
+                    continue;
+                }
+                None => {
+                    self.newcomers.push(newcomer);
+                    continue;
+                }
+            };
+            requests.push((newcomer, request));
+        }
+        requests
+    }
+
+    /// Tells the newcomer why it can not have what it asked for. It can ask again
+    /// a few times before it is dropped
+    pub fn refuse(&mut self, mut newcomer: Newcomer, reason: &str) {
+        if let Err(err) = newcomer
+            .connection
+            .send(&Message::Reject(reason.to_owned()))
+        {
+            println!("{}", err);
+        }
+
+        newcomer.bad_messages += 1;
+        if newcomer.bad_messages >= MAX_BAD_MESSAGES {
+            println!("{} was dropped after {}", newcomer.peer(), reason);
+            return;
+        }
+        self.newcomers.push(newcomer);
+    }
+
+    fn accept(&mut self) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            // the listener does not block, but talking to the player should
+            let connection = stream
+                .set_nonblocking(false)
+                .map_err(|err| err.to_string())
+                .and_then(|()| Connection::new(stream));
+
+            match connection {
+                Ok(connection) => {
+                    println!("{} connected", connection.peer());
+                    self.newcomers.push(Newcomer {
+                        connection,
+                        arrived: Instant::now(),
+                        bad_messages: 0,
+                    });
+                }
+                Err(err) => println!("{}", err),
+            }
+        }
+    }
+}
+
+impl Newcomer {
+    pub fn peer(&self) -> &str {
+        self.connection.peer()
+    }
+
+    /// Lets the newcomer into a game
+    pub fn into_connection(self) -> Connection {
+        self.connection
+    }
+}
@@ -0,0 +1,376 @@
+//! Playing over TCP. Every message is one line of text, so a game can be followed
+//! with a tool like netcat
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::rules::{
+    BChange, BoardPos, Clock, DrawReason, GameStatus, PieceType, Position, Side, TimeControl,
+    Variant, PROMOTION_TYPES,
+};
+
+pub mod client;
+pub mod connection;
+pub mod game;
+pub mod host;
+pub mod lobby;
+pub mod options;
+pub mod server;
+pub mod table;
+
+pub use client::Client;
+pub use connection::Connection;
+pub use game::Game;
+pub use host::{Host, HOST_GAME};
+pub use lobby::{Lobby, Newcomer, Request};
+pub use options::NetOptions;
+pub use server::Server;
+pub use table::{Event, Table};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// How long a player who lost the connection has to come back before they lose
+/// the game. Their clock does not run meanwhile
+pub const RECONNECT_GRACE: Duration = Duration::from_secs(60);
+
+/// A move as it is sent between players: the square of the piece and the square
+/// clicked to play it, like on the board, and the piece a pawn is promoted to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NetMove {
+    pub start: BoardPos,
+    pub click: BoardPos,
+    pub promotion: Option<PieceType>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Message {
+    /// Asks for a game, sent by a player as soon as they connect
+    Play,
+    /// Asks to watch a game without playing, the newest one if no id is given
+    Watch(Option<u32>),
+    /// Sent to a player when the game starts or they come back, what they need to
+    /// take their seat back if the connection is lost and how long they have to
+    Seat {
+        game: u32,
+        token: String,
+        grace: Duration,
+    },
+    /// Takes back a seat with the token the host sent, instead of asking for a new game
+    Resume {
+        game: u32,
+        token: String,
+    },
+    /// Sent by the host when someone joins or the game is set up again. `game` is
+    /// the game so far, written as a UCI `position` command. Spectators get no side
+    Welcome {
+        side: Option<Side>,
+        variant: Variant,
+        game: String,
+    },
+    /// A move to play. The host sends every move it accepts to the players
+    Move(NetMove),
+    /// The host would not play the last move sent, and why
+    Reject(String),
+    Resign(Side),
+    /// The time each side had left when the last move was played, and whose
+    /// time is running now
+    Clock {
+        control: Option<TimeControl>,
+        white: Duration,
+        black: Duration,
+        running: Option<Side>,
+    },
+    /// The side ran out of time
+    Timeout(Side),
+    /// The side to move claimed a draw by repetition or the fifty move rule
+    Draw,
+    /// Something for the player to read, like waiting for an opponent
+    Notice(String),
+}
+
+impl NetMove {
+    pub fn from_change(change: &BChange) -> Self {
+        let promotion = match change {
+            BChange::Promotion { piece_type, .. } | BChange::PushPremote { piece_type, .. } => {
+                Some(*piece_type)
+            }
+            _ => None,
+        };
+
+        Self {
+            start: change.mover_squares().0,
+            click: change.click_pos_to_activate_change(),
+            promotion,
+        }
+    }
+
+    /// Finds the change this move stands for if `side` is allowed to play it
+    pub fn validate(&self, position: &Position, side: Side) -> Result<BChange, String> {
+        if position.game_status().is_over() {
+            return Err("The game is over".to_owned());
+        }
+
+        if !position.is_turn(side) {
+            return Err(format!("It is not {:?}'s turn", side));
+        }
+
+        // `check_valid_change` expects a piece on the start square
+        match position.get_piece(self.start) {
+            Some(piece) if piece.side == side => (),
+            _ => {
+                return Err(format!(
+                    "{:?} has no piece on {}",
+                    side,
+                    self.start.to_algebraic()
+                ))
+            }
+        }
+
+        let change = position
+            .check_valid_change(self.start, self.click)
+            .ok_or_else(|| format!("{} is not a legal move", self))?;
+
+        match (change.is_promotion(), self.promotion) {
+            (true, Some(piece_type)) if PROMOTION_TYPES.contains(&piece_type) => {
+                Ok(change.convert_to_promotion(piece_type))
+            }
+            (true, _) => Err(format!("{} needs a piece to promote to", self)),
+            (false, None) => Ok(change),
+            (false, Some(_)) => Err(format!("{} is not a promotion", self)),
+        }
+    }
+}
+
+impl fmt::Display for NetMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.start.to_algebraic(),
+            self.click.to_algebraic()
+        )?;
+
+        if let Some(piece_type) = self.promotion {
+            write!(f, " {}", piece_type.letter().to_ascii_lowercase())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Message {
+    /// How the game ended when it can not be seen from the moves, for players who
+    /// join or come back after it ended
+    pub fn ending(position: &Position) -> Option<Self> {
+        match position.game_status() {
+            GameStatus::Resignation { winner } => Some(Message::Resign(winner.opposite())),
+            GameStatus::Timeout { .. }
+            | GameStatus::Draw {
+                reason: DrawReason::TimeoutVsInsufficientMaterial,
+            } => Some(Message::Timeout(position.turn())),
+            GameStatus::Draw {
+                reason: DrawReason::ThreefoldRepetition | DrawReason::FiftyMoves,
+            } => Some(Message::Draw),
+            _ => None,
+        }
+    }
+
+    /// The times on a clock, for players who follow it
+    pub fn clock(clock: &Clock) -> Self {
+        Message::Clock {
+            control: clock.control(),
+            white: clock.time_left(Side::White).unwrap_or_default(),
+            black: clock.time_left(Side::Black).unwrap_or_default(),
+            running: clock.running(),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let mut tokens = rest.split_whitespace();
+
+        match command {
+            "welcome" => {
+                let side = match tokens.next() {
+                    Some("none") => None,
+                    text => Some(parse_side(text)?),
+                };
+                let variant = parse_variant(tokens.next())?;
+                let game = tokens.collect::<Vec<_>>().join(" ");
+
+                Ok(Message::Welcome {
+                    side,
+                    variant,
+                    game,
+                })
+            }
+            "move" => {
+                let mut square = || match tokens.next() {
+                    Some(text) => BoardPos::from_algebraic(text),
+                    None => Err(format!("Missing square in: {}", line)),
+                };
+                let start = square()?;
+                let click = square()?;
+
+                let promotion = match tokens.next() {
+                    Some(text) => match text.chars().next().and_then(PieceType::from_letter) {
+                        Some(piece_type) if text.len() == 1 => Some(piece_type),
+                        _ => return Err(format!("Invalid piece: {}", text)),
+                    },
+                    None => None,
+                };
+
+                Ok(Message::Move(NetMove {
+                    start,
+                    click,
+                    promotion,
+                }))
+            }
+            "play" => Ok(Message::Play),
+            "watch" => match tokens.next() {
+                Some(text) => match text.parse::<u32>() {
+                    Ok(id) => Ok(Message::Watch(Some(id))),
+                    Err(_) => Err(format!("Invalid game: {}", text)),
+                },
+                None => Ok(Message::Watch(None)),
+            },
+            "clock" => {
+                let control = match tokens.next() {
+                    Some("none") => None,
+                    Some(text) => Some(TimeControl::parse(text)?),
+                    None => return Err(format!("Missing time control in: {}", line)),
+                };
+                let mut time = || match tokens.next().map(|text| text.parse::<u64>()) {
+                    Some(Ok(millis)) => Ok(Duration::from_millis(millis)),
+                    _ => Err(format!("Invalid time in: {}", line)),
+                };
+                let white = time()?;
+                let black = time()?;
+                let running = match tokens.next() {
+                    Some("none") => None,
+                    text => Some(parse_side(text)?),
+                };
+
+                Ok(Message::Clock {
+                    control,
+                    white,
+                    black,
+                    running,
+                })
+            }
+            "seat" | "resume" => {
+                let game = match tokens.next().map(|text| text.parse::<u32>()) {
+                    Some(Ok(game)) => game,
+                    _ => return Err(format!("Invalid game in: {}", line)),
+                };
+                let token = match tokens.next() {
+                    Some(token) => token.to_owned(),
+                    None => return Err(format!("Missing token in: {}", line)),
+                };
+
+                if command == "resume" {
+                    return Ok(Message::Resume { game, token });
+                }
+                match tokens.next().map(|text| text.parse::<u64>()) {
+                    Some(Ok(millis)) => Ok(Message::Seat {
+                        game,
+                        token,
+                        grace: Duration::from_millis(millis),
+                    }),
+                    _ => Err(format!("Invalid grace period in: {}", line)),
+                }
+            }
+            "timeout" => Ok(Message::Timeout(parse_side(tokens.next())?)),
+            "draw" => Ok(Message::Draw),
+            "reject" => Ok(Message::Reject(rest.trim().to_owned())),
+            "resign" => Ok(Message::Resign(parse_side(tokens.next())?)),
+            "notice" => Ok(Message::Notice(rest.trim().to_owned())),
+            _ => Err(format!("Unknown message: {}", line)),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Play => write!(f, "play"),
+            Message::Watch(Some(id)) => write!(f, "watch {}", id),
+            Message::Watch(None) => write!(f, "watch"),
+            Message::Seat { game, token, grace } => {
+                write!(f, "seat {} {} {}", game, token, grace.as_millis())
+            }
+            Message::Resume { game, token } => write!(f, "resume {} {}", game, token),
+            Message::Welcome {
+                side,
+                variant,
+                game,
+            } => write!(
+                f,
+                "welcome {} {} {}",
+                side.map_or("none", side_name),
+                variant_name(*variant),
+                game
+            ),
+            Message::Move(net_move) => write!(f, "move {}", net_move),
+            Message::Reject(reason) => write!(f, "reject {}", reason),
+            Message::Resign(side) => write!(f, "resign {}", side_name(*side)),
+            Message::Clock {
+                control,
+                white,
+                black,
+                running,
+            } => write!(
+                f,
+                "clock {} {} {} {}",
+                control.map_or("none".to_owned(), |control| control.notation()),
+                white.as_millis(),
+                black.as_millis(),
+                running.map_or("none", side_name)
+            ),
+            Message::Timeout(side) => write!(f, "timeout {}", side_name(*side)),
+            Message::Draw => write!(f, "draw"),
+            Message::Notice(text) => write!(f, "notice {}", text),
+        }
+    }
+}
+
+/// A secret for a player's seat, different every time
+pub fn new_token() -> String {
+    // every `RandomState` is seeded differently, which is random enough to keep a
+    // seat from being taken by someone guessing
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    format!("{:016x}", hasher.finish())
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::White => "white",
+        Side::Black => "black",
+    }
+}
+
+fn parse_side(text: Option<&str>) -> Result<Side, String> {
+    match text {
+        Some("white") => Ok(Side::White),
+        Some("black") => Ok(Side::Black),
+        _ => Err(format!("Invalid side: {}", text.unwrap_or(""))),
+    }
+}
+
+fn variant_name(variant: Variant) -> &'static str {
+    match variant {
+        Variant::RookPush => "rookpush",
+        Variant::Standard => "standard",
+    }
+}
+
+fn parse_variant(text: Option<&str>) -> Result<Variant, String> {
+    match text {
+        Some("rookpush") => Ok(Variant::RookPush),
+        Some("standard") => Ok(Variant::Standard),
+        _ => Err(format!("Invalid variant: {}", text.unwrap_or(""))),
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use super::{DEFAULT_PORT, RECONNECT_GRACE};
+use crate::rules::{TimeControl, Variant};
+
+/// The command line options for playing over the network, read the same way by
+/// the game, the client and the server
+pub struct NetOptions {
+    /// Port to wait for another player on
+    pub host: Option<u16>,
+    /// Port the server listens on
+    pub port: u16,
+    /// Address of a host to play against, or to watch a game on
+    pub join: Option<String>,
+    pub watch: bool,
+    /// The server game to watch, instead of the newest one
+    pub game: Option<u32>,
+    /// A seat to take back after losing the connection, the game and its token
+    pub resume: Option<(u32, String)>,
+    pub variant: Variant,
+    pub time_control: Option<TimeControl>,
+    /// How long a player who loses the connection has to come back
+    pub grace: Duration,
+}
+
+impl Default for NetOptions {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: DEFAULT_PORT,
+            join: None,
+            watch: false,
+            game: None,
+            resume: None,
+            variant: Variant::default(),
+            time_control: None,
+            grace: RECONNECT_GRACE,
+        }
+    }
+}
+
+impl NetOptions {
+    /// Reads `arg`, with its value taken from `args`, when it is one of the options
+    /// in `wanted`. Returns false for any other argument, for the program to read
+    pub fn read(
+        &mut self,
+        wanted: &[&str],
+        arg: &str,
+        args: &mut impl Iterator<Item = String>,
+    ) -> Result<bool, String> {
+        if !wanted.contains(&arg) {
+            return Ok(false);
+        }
+
+        // every option is followed by a value
+        let value = args.next();
+        let expected = |what: &str| Err(format!("{} should be followed by {}", arg, what));
+        match arg {
+            "--host" | "--port" => match value.and_then(|port| port.parse::<u16>().ok()) {
+                Some(port) if arg == "--host" => self.host = Some(port),
+                Some(port) => self.port = port,
+                None => return expected(&format!("a port like {}", DEFAULT_PORT)),
+            },
+            "--join" | "--watch" => match value {
+                Some(address) => {
+                    self.watch = arg == "--watch";
+                    // the port can be left out when it is the usual one
+                    self.join = match address.contains(':') {
+                        true => Some(address),
+                        false => Some(format!("{}:{}", address, DEFAULT_PORT)),
+                    };
+                }
+                None => return expected("the host's address"),
+            },
+            "--game" => match value.and_then(|id| id.parse::<u32>().ok()) {
+                Some(id) => self.game = Some(id),
+                None => return expected("the number of a game"),
+            },
+            "--resume" => {
+                let seat = value.as_deref().and_then(|seat| seat.split_once(':'));
+                match seat.map(|(game, token)| (game.parse::<u32>(), token)) {
+                    Some((Ok(game), token)) => self.resume = Some((game, token.to_owned())),
+                    _ => return expected("the seat the host gave, like 3:9f2c41d07e6ab815"),
+                }
+            }
+            "--variant" => match value.as_deref() {
+                Some("rookpush") => self.variant = Variant::RookPush,
+                Some("standard") => self.variant = Variant::Standard,
+                _ => return expected("rookpush or standard"),
+            },
+            "--time" => match value.map(|text| TimeControl::parse(&text)) {
+                Some(Ok(control)) => self.time_control = Some(control),
+                Some(Err(err)) => return Err(err),
+                None => return expected("a time control like 5+3"),
+            },
+            "--grace" => match value.and_then(|secs| secs.parse::<u64>().ok()) {
+                Some(secs) => self.grace = Duration::from_secs(secs),
+                None => return expected("a number of seconds"),
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,178 @@
+use std::thread;
+use std::time::Duration;
+
+use super::{Connection, Game, Lobby, Message, Newcomer, Request, Table, RECONNECT_GRACE};
+use crate::rules::{Clock, Position, Side, TimeControl, Variant};
+
+/// How long the server sleeps when nobody has sent anything
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Hosts any number of games at once without a window. Players are paired up in
+/// the order they ask for a game, the first of the two plays white
+pub struct Server {
+    lobby: Lobby,
+    variant: Variant,
+    time_control: Option<TimeControl>,
+    /// A player who asked for a game and has no opponent yet
+    waiting: Option<Connection>,
+    games: Vec<Game>,
+    next_id: u32,
+    /// How long players who lose the connection have to come back
+    grace: Duration,
+}
+
+impl Server {
+    pub fn bind(
+        address: &str,
+        variant: Variant,
+        time_control: Option<TimeControl>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            lobby: Lobby::bind(address)?,
+            variant,
+            time_control,
+            waiting: None,
+            games: Vec::new(),
+            next_id: 1,
+            grace: RECONNECT_GRACE,
+        })
+    }
+
+    /// Changes how long players who lose the connection have to come back before
+    /// they lose the game
+    pub fn with_grace_period(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// The address players connect to, with the port picked when binding to port 0
+    pub fn local_addr(&self) -> Result<String, String> {
+        self.lobby.local_addr()
+    }
+
+    pub fn games(&self) -> &[Game] {
+        &self.games
+    }
+
+    /// Serves games until the process is stopped
+    pub fn run(&mut self) {
+        loop {
+            self.step();
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Accepts new connections and handles every message that has arrived
+    pub fn step(&mut self) {
+        self.greet();
+
+        for game in self.games.iter_mut() {
+            // the players have been told what happened, there is no board here to show it on
+            game.step();
+        }
+
+        self.games.retain(|game| {
+            let open = !game.is_done();
+            if !open {
+                println!("Game {} is over", game.id());
+            }
+            open
+        });
+    }
+
+    /// Finds out what each new connection wants
+    fn greet(&mut self) {
+        for (newcomer, request) in self.lobby.requests() {
+            match request {
+                Request::Play => self.find_opponent(newcomer.into_connection()),
+                Request::Watch(id) => self.watch(newcomer, id),
+                Request::Resume { game, token } => self.resume(newcomer, game, &token),
+            }
+        }
+
+        if let Some(waiting) = &mut self.waiting {
+            // nothing is expected from a waiting player, but it could still leave
+            while waiting.poll().is_some() {}
+            if !waiting.is_open() {
+                println!("{} stopped waiting", waiting.peer());
+                self.waiting = None;
+            }
+        }
+    }
+
+    fn find_opponent(&mut self, mut player: Connection) {
+        let Some(opponent) = self.waiting.take() else {
+            send(
+                &mut player,
+                &Message::Notice("Waiting for an opponent".to_owned()),
+            );
+            self.waiting = Some(player);
+            return;
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        println!(
+            "Game {} started, {} against {}",
+            id,
+            opponent.peer(),
+            player.peer()
+        );
+
+        let position = Position::new().with_variant(self.variant);
+        let clock = Clock::new(self.time_control);
+        let table = Table::new(id).with_grace_period(self.grace);
+        let mut game = Game::new(table, position, clock);
+
+        let notice = Message::Notice(format!("Game {} started", id));
+        for (side, mut connection) in [(Side::White, opponent), (Side::Black, player)] {
+            send(&mut connection, &notice);
+            game.seat(side, connection);
+        }
+        self.games.push(game);
+    }
+
+    /// Gives a player who lost the connection their seat back
+    fn resume(&mut self, newcomer: Newcomer, id: u32, token: &str) {
+        let Some(game) = self.games.iter_mut().find(|game| game.id() == id) else {
+            self.lobby
+                .refuse(newcomer, &format!("There is no game {}", id));
+            return;
+        };
+
+        match game.seat_of(token) {
+            Some(side) => game.resume(side, newcomer.into_connection()),
+            None => {
+                let reason = format!("That is not a seat in game {}", id);
+                self.lobby.refuse(newcomer, &reason);
+            }
+        }
+    }
+
+    /// Lets a connection follow the game with the id, or the newest game
+    fn watch(&mut self, newcomer: Newcomer, id: Option<u32>) {
+        let game = match id {
+            Some(id) => self.games.iter_mut().find(|game| game.id() == id),
+            None => self.games.last_mut(),
+        };
+
+        let Some(game) = game else {
+            let reason = match id {
+                Some(id) => format!("There is no game {}", id),
+                None => "There are no games to watch".to_owned(),
+            };
+            self.lobby.refuse(newcomer, &reason);
+            return;
+        };
+
+        game.watch(newcomer.into_connection());
+    }
+}
+
+/// Sends a message, a player it can not reach is dropped once the connection is
+/// seen to be closed
+fn send(connection: &mut Connection, message: &Message) {
+    if let Err(err) = connection.send(message) {
+        println!("{}", err);
+    }
+}
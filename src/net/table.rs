@@ -0,0 +1,369 @@
+use std::time::{Duration, Instant};
+
+use super::{new_token, Connection, Message, NetMove, RECONNECT_GRACE};
+use crate::rules::{BChange, Clock, Position, Side, TimeControl};
+
+/// Everyone who follows a game over the network: the players in their seats and
+/// the spectators. The server keeps one for each of its games, and a player who
+/// hosts a game keeps one for the guest and spectators
+pub struct Table {
+    id: u32,
+    seats: Vec<Seat>,
+    /// Connections that get every move but can not play any
+    spectators: Vec<Connection>,
+    /// How long players who lose the connection have to come back
+    grace: Duration,
+    /// The game as everyone here last heard about it, written like a UCI position
+    sent: Option<String>,
+    /// The time control and running side of the last clock sent
+    sent_clock: (Option<TimeControl>, Option<Side>),
+    /// How the game ended, once everyone has been told
+    sent_ending: Option<Message>,
+    /// Notices about the players coming and going, sent with the next sync
+    notices: Vec<String>,
+}
+
+/// A side played from another machine, kept for a while after the player loses
+/// the connection so they can come back
+struct Seat {
+    side: Side,
+    /// Sent back by the player to take the seat again
+    token: String,
+    /// Gone while the player is away
+    connection: Option<Connection>,
+    /// When the player lost the connection to a game that was not over yet
+    left: Option<Instant>,
+}
+
+/// Something a player at the table did to the game, for whoever keeps the game
+/// to play out
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event {
+    /// A legal move by the side to move
+    Move {
+        side: Side,
+        change: BChange,
+    },
+    Resign(Side),
+    /// The player lost the connection and did not come back in time
+    Abandoned(Side),
+    /// The side ran out of time. Only the game's clock says so, never a player
+    Timeout(Side),
+}
+
+impl Table {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            seats: Vec::new(),
+            spectators: Vec::new(),
+            grace: RECONNECT_GRACE,
+            sent: None,
+            sent_clock: (None, None),
+            sent_ending: None,
+            notices: Vec::new(),
+        }
+    }
+
+    /// Changes how long players who lose the connection have to come back before
+    /// they lose the game
+    pub fn with_grace_period(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// Whether a new player can sit down to play `side`, and why not
+    pub fn can_seat(&self, side: Side) -> Result<(), String> {
+        match self.seats.iter().find(|seat| seat.side == side) {
+            Some(seat) if seat.left.is_some() => {
+                Err(format!("The game is waiting for {:?} to come back", side))
+            }
+            Some(seat) if seat.connection.is_some() => {
+                Err("The game already has two players, send watch to watch it".to_owned())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Gives `side` to a new player, who gets the token to come back with and the
+    /// game so far
+    pub fn seat(
+        &mut self,
+        side: Side,
+        mut connection: Connection,
+        position: &Position,
+        clock: &Clock,
+    ) {
+        self.sync(position, clock);
+
+        let token = new_token();
+        let seat = Message::Seat {
+            game: self.id,
+            token: token.clone(),
+            grace: self.grace,
+        };
+        send(&mut connection, &seat);
+        welcome(&mut connection, Some(side), position, clock);
+
+        self.seats.retain(|seat| seat.side != side);
+        self.seats.push(Seat {
+            side,
+            token,
+            connection: Some(connection),
+            left: None,
+        });
+    }
+
+    /// The side of the seat the token is for
+    pub fn seat_of(&self, token: &str) -> Option<Side> {
+        self.seats
+            .iter()
+            .find(|seat| seat.token == token)
+            .map(|seat| seat.side)
+    }
+
+    /// Puts a player back in their seat with the game so far
+    pub fn resume(
+        &mut self,
+        side: Side,
+        mut connection: Connection,
+        position: &Position,
+        clock: &Clock,
+    ) {
+        self.sync(position, clock);
+        println!(
+            "{} is back in game {} as {:?}",
+            connection.peer(),
+            self.id,
+            side
+        );
+
+        send(
+            &mut connection,
+            &Message::Notice(format!("Back in game {}", self.id)),
+        );
+        welcome(&mut connection, Some(side), position, clock);
+        self.broadcast(&Message::Notice(format!("{:?} is back", side)));
+
+        // an old connection that never noticed it was lost is dropped here
+        if let Some(seat) = self.seats.iter_mut().find(|seat| seat.side == side) {
+            // a player who came back from another program has not been told the grace yet
+            let message = Message::Seat {
+                game: self.id,
+                token: seat.token.clone(),
+                grace: self.grace,
+            };
+            send(&mut connection, &message);
+            seat.connection = Some(connection);
+            seat.left = None;
+        }
+    }
+
+    /// Lets a connection follow the game from now on
+    pub fn watch(&mut self, mut connection: Connection, position: &Position, clock: &Clock) {
+        self.sync(position, clock);
+        println!("{} is watching game {}", connection.peer(), self.id);
+
+        send(
+            &mut connection,
+            &Message::Notice(format!("Watching game {}", self.id)),
+        );
+        welcome(&mut connection, None, position, clock);
+        self.spectators.push(connection);
+    }
+
+    /// Whether the player of `side` has lost the connection and still has time to
+    /// come back, which stops their clock
+    pub fn is_away(&self, side: Side) -> bool {
+        self.seats
+            .iter()
+            .any(|seat| seat.side == side && seat.left.is_some())
+    }
+
+    /// Nobody is left in any of the seats
+    pub fn is_empty(&self) -> bool {
+        self.seats.iter().all(|seat| seat.connection.is_none())
+    }
+
+    /// The next thing a player did to the game. Messages that can not be played
+    /// are rejected here, so this is called until it returns `None`, with the game
+    /// brought up to date in between
+    pub fn poll(&mut self, position: &Position) -> Option<Event> {
+        let over = position.game_status().is_over();
+
+        for seat in self.seats.iter_mut() {
+            let Some(connection) = &mut seat.connection else {
+                continue;
+            };
+
+            while let Some(message) = connection.poll() {
+                let reason = match message {
+                    Ok(Message::Move(net_move)) => match net_move.validate(position, seat.side) {
+                        Ok(change) => {
+                            return Some(Event::Move {
+                                side: seat.side,
+                                change,
+                            })
+                        }
+                        Err(reason) => reason,
+                    },
+                    Ok(Message::Resign(side)) if side == seat.side && !over => {
+                        return Some(Event::Resign(side))
+                    }
+                    Ok(Message::Resign(side)) if side == seat.side => continue,
+                    Ok(message) => format!("Players can not send: {}", message),
+                    Err(err) => err,
+                };
+                send(connection, &Message::Reject(reason));
+            }
+        }
+
+        for spectator in self.spectators.iter_mut() {
+            while let Some(message) = spectator.poll() {
+                let reason = match message {
+                    Ok(message) => format!("Spectators can not send: {}", message),
+                    Err(err) => err,
+                };
+                send(spectator, &Message::Reject(reason));
+            }
+        }
+        self.spectators.retain(|spectator| spectator.is_open());
+
+        // a player who leaves an unfinished game has a while to come back before losing it
+        let mut abandoned = None;
+        for seat in self.seats.iter_mut() {
+            if let Some(connection) = seat.connection.as_ref().filter(|c| !c.is_open()) {
+                println!("{} left game {}", connection.peer(), self.id);
+                seat.connection = None;
+                if !over {
+                    seat.left = Some(Instant::now());
+                    self.notices.push(format!(
+                        "{:?} lost the connection, waiting {} seconds for them to come back",
+                        seat.side,
+                        self.grace.as_secs()
+                    ));
+                }
+            }
+
+            let gone = seat.left.is_some_and(|left| left.elapsed() >= self.grace);
+            if over {
+                seat.left = None;
+            } else if gone && abandoned.is_none() {
+                seat.left = None;
+                println!("{:?} did not come back to game {}", seat.side, self.id);
+                self.notices.push(format!("{:?} left the game", seat.side));
+                abandoned = Some(Event::Abandoned(seat.side));
+            }
+        }
+
+        abandoned
+    }
+
+    /// Tells everyone what happened to the game since they last heard: the moves
+    /// played, the clock and how the game ended. A game that changed some other
+    /// way, like a new game, is sent again from the start
+    pub fn sync(&mut self, position: &Position, clock: &Clock) {
+        let game = position.to_uci_position();
+        let clock_state = (clock.control(), clock.running());
+        let ending = Message::ending(position);
+
+        // a game can only go on from where it was if it had not ended
+        let new_moves = match (&self.sent, &self.sent_ending) {
+            (Some(_), Some(sent)) if ending.as_ref() != Some(sent) => None,
+            (Some(sent), _) => moves_since(sent, &game),
+            (None, _) => None,
+        };
+
+        let mut messages = Vec::new();
+        match new_moves {
+            Some(0) if clock_state == self.sent_clock => (),
+            Some(0) => messages.push(Message::clock(clock)),
+            Some(played) => {
+                let history = position.history();
+                messages.extend(
+                    history[history.len() - played..]
+                        .iter()
+                        .map(|record| Message::Move(NetMove::from_change(&record.change))),
+                );
+                messages.push(Message::clock(clock));
+            }
+            None => {
+                for seat in self.seats.iter_mut() {
+                    if let Some(connection) = &mut seat.connection {
+                        welcome(connection, Some(seat.side), position, clock);
+                    }
+                }
+                for spectator in self.spectators.iter_mut() {
+                    welcome(spectator, None, position, clock);
+                }
+            }
+        }
+
+        messages.extend(self.notices.drain(..).map(Message::Notice));
+        if new_moves.is_some() && ending != self.sent_ending {
+            messages.extend(ending.clone());
+        }
+        for message in messages.iter() {
+            self.broadcast(message);
+        }
+
+        self.sent = Some(game);
+        self.sent_clock = clock_state;
+        self.sent_ending = ending;
+    }
+
+    /// Sends a message to everyone still connected
+    fn broadcast(&mut self, message: &Message) {
+        let everyone = self
+            .seats
+            .iter_mut()
+            .filter_map(|seat| seat.connection.as_mut())
+            .chain(self.spectators.iter_mut());
+
+        for connection in everyone {
+            send(connection, message);
+        }
+    }
+}
+
+/// How many moves were played to get from the game `sent` to `game`, if that is
+/// how it got there
+pub(super) fn moves_since(sent: &str, game: &str) -> Option<usize> {
+    let rest = game.strip_prefix(sent)?;
+    let played = rest
+        .split_whitespace()
+        .filter(|&token| token != "moves")
+        .count();
+    (rest.is_empty() || rest.starts_with(' ')).then_some(played)
+}
+
+/// Tells someone who has just joined their side, the game so far, the clock and
+/// how the game ended if it did
+fn welcome(connection: &mut Connection, side: Option<Side>, position: &Position, clock: &Clock) {
+    let welcome = Message::Welcome {
+        side,
+        variant: position.variant(),
+        game: position.to_uci_position(),
+    };
+    send(connection, &welcome);
+    send(connection, &Message::clock(clock));
+    if let Some(ending) = Message::ending(position) {
+        send(connection, &ending);
+    }
+}
+
+/// Sends a message, a player it can not reach is dropped once the connection is
+/// seen to be closed
+fn send(connection: &mut Connection, message: &Message) {
+    if let Err(err) = connection.send(message) {
+        println!("{}", err);
+    }
+}
@@ -0,0 +1,289 @@
+use bevy::prelude::*;
+use chess::{
+    net::{Client, Event, Host, Message, NetMove},
+    rules::{BChange, Clock, Position, Side},
+};
+
+use crate::{
+    board::Board,
+    game_over::GameOver,
+    history::HistoryView,
+    resources::{
+        Computer, HiglightedSquares, PgnReplay, PromotionPicker, SelectedSquare, StartOptions,
+    },
+    systems::apply_change,
+};
+
+pub struct NetworkPlugin;
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(receive_messages);
+    }
+}
+
+/// A game against a player on another machine. The host checks every move and
+/// sends the ones it plays to the guest and anyone watching, who only play what
+/// the host sends. The host can also be the `server` binary, which plays no side itself
+pub struct Network {
+    role: Role,
+    /// A move has been sent to the host and it has not answered yet
+    waiting: bool,
+}
+
+enum Role {
+    Offline,
+    Host(Host),
+    Guest(Client),
+}
+
+impl Network {
+    /// Listens or connects if the command line asked for a network game
+    pub fn start(options: &StartOptions) -> Self {
+        let role = match (&options.net.host, &options.net.join) {
+            (Some(port), _) => match Host::bind(&format!("0.0.0.0:{}", port), Side::White) {
+                Ok(host) => {
+                    println!("Waiting for a player to join on port {}", port);
+                    Role::Host(host.with_grace_period(options.net.grace))
+                }
+                Err(err) => {
+                    println!("{}", err);
+                    Role::Offline
+                }
+            },
+            (None, Some(address)) => {
+                let hello = match (&options.net.resume, options.net.watch) {
+                    (Some((game, token)), _) => Message::Resume {
+                        game: *game,
+                        token: token.clone(),
+                    },
+                    (None, true) => Message::Watch(options.net.game),
+                    (None, false) => Message::Play,
+                };
+
+                match Client::connect(address, &hello) {
+                    Ok(client) => {
+                        println!("Connected to {}", address);
+                        Role::Guest(client)
+                    }
+                    Err(err) => {
+                        println!("{}, playing on this machine instead", err);
+                        Role::Offline
+                    }
+                }
+            }
+            (None, None) => Role::Offline,
+        };
+
+        Self {
+            role,
+            waiting: false,
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        !matches!(self.role, Role::Offline)
+    }
+
+    /// Whether someone else decides which moves are played, including when watching
+    pub fn is_guest(&self) -> bool {
+        matches!(self.role, Role::Guest(_))
+    }
+
+    /// The side played on this machine, if it is a network game and the host has said
+    pub fn side(&self) -> Option<Side> {
+        match &self.role {
+            Role::Offline => None,
+            Role::Host(host) => Some(host.side()),
+            Role::Guest(client) => client.side(),
+        }
+    }
+
+    /// False for a guest that has lost the connection to the host
+    pub fn is_connected(&self) -> bool {
+        match &self.role {
+            Role::Guest(client) => client.is_connected(),
+            _ => true,
+        }
+    }
+
+    /// Whether a move for `side` can be made on this machine now
+    pub fn can_move(&self, side: Side) -> bool {
+        match &self.role {
+            Role::Offline => true,
+            Role::Host(_) => self.side() == Some(side),
+            Role::Guest(client) => {
+                client.side() == Some(side) && client.is_connected() && !self.waiting
+            }
+        }
+    }
+
+    /// Asks the host to play a move made on this machine, it is played once the
+    /// host sends it back
+    pub fn request_move(&mut self, change: &BChange) {
+        if let Role::Guest(client) = &mut self.role {
+            match client.send(&Message::Move(NetMove::from_change(change))) {
+                Ok(()) => self.waiting = true,
+                Err(err) => println!("{}", err),
+            }
+        }
+    }
+
+    /// Tells the host about something that happened on this machine, like a
+    /// resignation. Everything that happens on the host reaches the others with
+    /// the moves
+    pub fn send(&mut self, message: &Message) {
+        if let Role::Guest(client) = &mut self.role {
+            if let Err(err) = client.send(message) {
+                println!("{}", err);
+            }
+        }
+    }
+}
+
+/// Plays the moves the other player sends
+fn receive_messages(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut clock: ResMut<Clock>,
+    mut network: ResMut<Network>,
+    mut view: ResMut<HistoryView>,
+    mut game_over: ResMut<GameOver>,
+    mut picker: ResMut<PromotionPicker>,
+    mut selected: ResMut<SelectedSquare>,
+    mut highlighted: ResMut<HiglightedSquares>,
+    mut computer: ResMut<Computer>,
+    replay: Res<PgnReplay>,
+) {
+    let network = &mut *network;
+    match &mut network.role {
+        Role::Offline => (),
+        Role::Host(host) => {
+            // what was played on the board since is played in the host's game, on its
+            // clock. A saved game being replayed has no times, the clock starts after it
+            let live = view.live_position(&board);
+            match replay.moves.is_empty() {
+                true => host.catch_up(live, clock.control()),
+                false => host.set_up(live.clone(), Clock::new(clock.control())),
+            }
+
+            for event in host.step() {
+                // whatever happens in the game brings it back on the board
+                view.back_to_game(&mut commands, &mut board);
+
+                match event {
+                    Event::Move { change, .. } => {
+                        apply_change(&mut commands, &mut board, &mut clock, change)
+                    }
+                    Event::Resign(side) => {
+                        board.resign(side);
+                        println!("{:?} resigned", side);
+                    }
+                    Event::Abandoned(side) => {
+                        board.resign(side);
+                        println!("{:?} did not come back and lost the game", side);
+                    }
+                    Event::Timeout(side) => {
+                        computer.stop_thinking();
+                        picker.close(&mut commands);
+                        board.time_out(side);
+                        println!("{:?} ran out of time", side);
+                    }
+                }
+            }
+
+            // the clock shown is the one the game is played on
+            *clock = host.game().clock().clone();
+        }
+        Role::Guest(client) => {
+            let over = view.live_position(&board).game_status().is_over();
+            while let Some(message) = client.poll(over) {
+                match message {
+                    Ok(Message::Welcome {
+                        side,
+                        variant,
+                        game,
+                    }) => match Position::from_uci_position(&game, variant) {
+                        Ok(position) => {
+                            match side {
+                                Some(side) => println!("Playing {:?}", side),
+                                None => println!("Watching the game"),
+                            }
+                            network.waiting = false;
+
+                            view.clear();
+                            board.set_position(&mut commands, position);
+                            *game_over = GameOver::default();
+                            picker.close(&mut commands);
+                            selected.piece = None;
+                            selected.tile = None;
+                            selected.changed = true;
+                            highlighted.squares.clear();
+                        }
+                        Err(err) => println!("Could not load the host's game: {}", err),
+                    },
+                    Ok(Message::Move(net_move)) => {
+                        view.back_to_game(&mut commands, &mut board);
+
+                        let turn = board.position().turn();
+                        match net_move.validate(board.position(), turn) {
+                            Ok(change) => {
+                                apply_change(&mut commands, &mut board, &mut clock, change)
+                            }
+                            Err(err) => println!("Could not play the host's move: {}", err),
+                        }
+                        network.waiting = false;
+                    }
+                    Ok(Message::Clock {
+                        control,
+                        white,
+                        black,
+                        running,
+                    }) => clock.follow(control, white, black, running),
+                    Ok(Message::Seat { game, token, .. }) => {
+                        println!(
+                            "If this window is closed, come back with --join {} --resume {}:{}",
+                            client.address(),
+                            game,
+                            token
+                        );
+                    }
+                    Ok(Message::Reject(reason)) => {
+                        println!("The host refused: {}", reason);
+                        network.waiting = false;
+                    }
+                    Ok(Message::Resign(resigned)) => {
+                        view.back_to_game(&mut commands, &mut board);
+                        if !board.position().game_status().is_over() {
+                            board.resign(resigned);
+                            println!("{:?} resigned", resigned);
+                        }
+                    }
+                    Ok(Message::Timeout(flagged)) => {
+                        view.back_to_game(&mut commands, &mut board);
+                        if !board.position().game_status().is_over() {
+                            board.time_out(flagged);
+                            println!("{:?} ran out of time", flagged);
+                        }
+                    }
+                    Ok(Message::Draw) => {
+                        view.back_to_game(&mut commands, &mut board);
+                        if !board.position().game_status().is_over() {
+                            match board.claim_draw() {
+                                Some(reason) => println!("Draw by {}", reason),
+                                None => println!("The host claimed a draw there is no claim to"),
+                            }
+                        }
+                    }
+                    Ok(Message::Notice(text)) => println!("{}", text),
+                    Ok(message) => println!("Unexpected message from the host: {}", message),
+                    Err(err) => println!("{}", err),
+                }
+            }
+
+            if !client.is_connected() {
+                network.waiting = false;
+            }
+        }
+    }
+}
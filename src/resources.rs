@@ -1,37 +1,124 @@
-use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use chess::{
+    ai::{external::ExternalEngine, search, SearchLimits},
+    net::NetOptions,
+    rules::{BChange, BoardPos, Clock, Piece, PieceType, Position, Side, Variant},
+};
 
 use crate::{
-    board::{BChange, Board, BoardPos, Piece},
+    board::Board,
     constants::{DESTROY_COLOR, MOVE_COLOR, PROMOTE_COLOR, PUSH_PREMOTE_COLOR, SWAP_COLOR},
+    network::Network,
 };
 
 pub struct ResourcesPlugin;
 
 impl Plugin for ResourcesPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SelectedSquare::default())
+        let options = StartOptions::from_args();
+
+        let mut computer = Computer::new(options.computer, options.think_time);
+        if let Some(path) = &options.engine {
+            computer.connect_engine(path);
+        }
+
+        let network = Network::start(&options);
+        if network.is_online() && computer.side.is_some() {
+            println!("The computer does not play in network games");
+            computer.side = None;
+        }
+
+        app.add_event::<NewGame>()
+            .insert_resource(computer)
+            .insert_resource(network)
+            .insert_resource(Clock::new(options.net.time_control))
+            .insert_resource(options)
+            .insert_resource(SelectedSquare::default())
             .insert_resource(HiglightedSquares {
                 squares: Vec::new(),
             })
+            .insert_resource(PromotionPicker::default())
+            .insert_resource(PgnReplay::default())
             .insert_resource(MouseInfo {
                 world_cords: None,
                 board_pos: None,
                 just_clicked: false,
-            });
+                just_released: false,
+            })
+            .insert_resource(DraggedPiece::default());
     }
 }
 
+/// Options read from the command line when the game starts
+pub struct StartOptions {
+    pub fen: Option<String>,
+    pub pgn: Option<String>,
+    pub computer: Option<Side>,
+    pub think_time: Duration,
+    pub engine: Option<String>,
+    /// The time control, and how to play over the network
+    pub net: NetOptions,
+}
+
+/// The side played by the computer, and its search while it is thinking
+pub struct Computer {
+    pub side: Option<Side>,
+    pub think_time: Duration,
+    /// The rules the computer knows how to play
+    pub variant: Variant,
+    pub task: Option<Task<Result<Option<BChange>, String>>>,
+    /// An external UCI engine that plays instead of the built in search
+    engine: Option<Arc<Mutex<ExternalEngine>>>,
+    stop: Arc<AtomicBool>,
+}
+
+/// Sent to start a new game, like pressing Ctrl+R
+pub struct NewGame;
+
+/// Moves from an imported PGN that are still waiting to be played
+#[derive(Default)]
+pub struct PgnReplay {
+    pub moves: VecDeque<String>,
+}
+
 pub struct MouseInfo {
     pub world_cords: Option<Vec3>,
     pub board_pos: Option<BoardPos>,
     pub just_clicked: bool,
+    pub just_released: bool,
+}
+
+/// The piece following the mouse while the button is held down, and the square
+/// it was picked up from
+#[derive(Default)]
+pub struct DraggedPiece {
+    pub piece: Option<(Entity, BoardPos)>,
 }
 
+#[derive(Default)]
 pub struct SelectedSquare {
     pub changed: bool,
     pub tile: Option<Entity>,
     pub piece: Option<Piece>,
 }
+
+/// The overlay that lets the player choose what a pawn is promoted to
+#[derive(Default)]
+pub struct PromotionPicker {
+    pub change: Option<BChange>,
+    pub options: Vec<(Entity, BoardPos, PieceType)>,
+}
+
 pub struct HiglightedSquares {
     pub squares: Vec<(Entity, Color)>,
 }
@@ -57,12 +144,158 @@ impl HiglightedSquares {
     }
 }
 
-impl Default for SelectedSquare {
+impl Default for StartOptions {
     fn default() -> Self {
         Self {
-            changed: false,
-            tile: None,
-            piece: None,
+            fen: None,
+            pgn: None,
+            computer: None,
+            think_time: Duration::from_secs(2),
+            engine: None,
+            net: NetOptions::default(),
+        }
+    }
+}
+
+impl StartOptions {
+    pub fn from_args() -> Self {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let wanted = [
+                "--host", "--join", "--watch", "--game", "--resume", "--time", "--grace",
+            ];
+            match options.net.read(&wanted, &arg, &mut args) {
+                Ok(true) => continue,
+                Ok(false) => (),
+                Err(err) => {
+                    println!("{}", err);
+                    continue;
+                }
+            }
+
+            match arg.as_str() {
+                "--fen" => options.fen = args.next(),
+                "--pgn" => options.pgn = args.next(),
+                "--computer" => match args.next().as_deref() {
+                    Some("white") => options.computer = Some(Side::White),
+                    Some("black") => options.computer = Some(Side::Black),
+                    _ => println!("--computer should be followed by white or black"),
+                },
+                "--engine" => options.engine = args.next(),
+                "--think-time" => match args.next().and_then(|secs| secs.parse::<f32>().ok()) {
+                    Some(secs) if secs > 0.0 => options.think_time = Duration::from_secs_f32(secs),
+                    _ => println!("--think-time should be followed by a number of seconds"),
+                },
+                other => println!("Unknown argument: {}", other),
+            }
+        }
+
+        options
+    }
+}
+
+impl PromotionPicker {
+    pub fn open(&mut self, commands: &mut Commands, board: &Board, change: BChange) {
+        self.close(commands);
+
+        let square = change
+            .promotion_square()
+            .expect("Only promotions should open the picker");
+        self.options = board.spawn_promotion_options(commands, board.position().turn(), square);
+        self.change = Some(change);
+    }
+
+    pub fn close(&mut self, commands: &mut Commands) {
+        for (entity, ..) in self.options.drain(..) {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        self.change = None;
+    }
+
+    pub fn choice_at(&self, board_pos: BoardPos) -> Option<PieceType> {
+        self.options
+            .iter()
+            .find(|(_, pos, _)| *pos == board_pos)
+            .map(|(_, _, piece_type)| *piece_type)
+    }
+}
+
+impl Computer {
+    pub fn new(side: Option<Side>, think_time: Duration) -> Self {
+        Self {
+            side,
+            think_time,
+            variant: Variant::default(),
+            task: None,
+            engine: None,
+            stop: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    /// Lets an external engine play for the computer. Engines only know normal chess,
+    /// unless they have a `RookPush` option like ours does
+    pub fn connect_engine(&mut self, path: &str) {
+        let mut engine = match ExternalEngine::start(path) {
+            Ok(engine) => engine,
+            Err(err) => {
+                println!("{}, the built in computer will play instead", err);
+                return;
+            }
+        };
+
+        self.variant = match engine.has_option("RookPush") {
+            true => match engine.set_option("RookPush", "true") {
+                Ok(()) => Variant::RookPush,
+                Err(err) => {
+                    println!("{}", err);
+                    return;
+                }
+            },
+            false => Variant::Standard,
+        };
+
+        println!("Playing against {}", engine.name());
+        self.side.get_or_insert(Side::Black);
+        self.engine = Some(Arc::new(Mutex::new(engine)));
+    }
+
+    pub fn plays(&self, side: Side) -> bool {
+        self.side == Some(side)
+    }
+
+    /// Starts searching for a move on a background thread, for at most `think_time`
+    pub fn start_thinking(&mut self, position: &Position, think_time: Duration) {
+        self.stop = Arc::new(AtomicBool::new(false));
+
+        let position = position.clone();
+        let stop = self.stop.clone();
+        let engine = self.engine.clone();
+
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            match engine {
+                Some(engine) => {
+                    let mut engine = engine.lock().map_err(|err| err.to_string())?;
+                    engine.best_move(&position, think_time, &stop)
+                }
+                None => {
+                    let limits = SearchLimits {
+                        time: Some(think_time),
+                        stop,
+                        ..Default::default()
+                    };
+                    Ok(search(&position, &limits).map(|result| result.best_move))
+                }
+            }
+        });
+        self.task = Some(task);
+    }
+
+    /// Throws away the search, for when the position it was started from changes
+    pub fn stop_thinking(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.task = None;
+    }
 }
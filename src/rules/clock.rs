@@ -0,0 +1,250 @@
+use std::fmt;
+use std::time::Duration;
+
+use super::Side;
+
+/// Time that does not count against a player on each move
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Delay {
+    #[default]
+    None,
+    /// The clock only starts counting down once the delay has passed (US delay)
+    Simple(Duration),
+    /// The time used is given back after the move, up to the delay (Bronstein delay)
+    Bronstein(Duration),
+}
+
+/// How much time each side gets, like `5+3` for five minutes and three seconds
+/// added after every move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+    pub delay: Delay,
+}
+
+impl TimeControl {
+    /// The time controls offered in the settings menu
+    pub const PRESETS: [TimeControl; 8] = [
+        TimeControl::new(1, 0),
+        TimeControl::new(3, 2),
+        TimeControl::new(5, 0),
+        TimeControl::new(5, 3),
+        TimeControl::new(10, 5),
+        TimeControl::new(15, 10),
+        TimeControl::new(5, 0).with_delay(Delay::Simple(Duration::from_secs(3))),
+        TimeControl::new(5, 0).with_delay(Delay::Bronstein(Duration::from_secs(3))),
+    ];
+
+    pub const fn new(minutes: u64, increment_secs: u64) -> Self {
+        Self {
+            base: Duration::from_secs(minutes * 60),
+            increment: Duration::from_secs(increment_secs),
+            delay: Delay::None,
+        }
+    }
+
+    pub const fn with_delay(mut self, delay: Delay) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Reads a time control written as minutes and seconds, `5+3` for an increment,
+    /// `5d3` for a US delay or `5b3` for a Bronstein delay
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid time control: {}", text);
+
+        let split = text.find(['+', 'd', 'b']).ok_or_else(invalid)?;
+        let minutes = text[..split].parse::<u64>().map_err(|_| invalid())?;
+        let secs = text[split + 1..].parse::<u64>().map_err(|_| invalid())?;
+
+        if minutes == 0 {
+            return Err(invalid());
+        }
+
+        let secs_duration = Duration::from_secs(secs);
+        Ok(match &text[split..split + 1] {
+            "+" => Self::new(minutes, secs),
+            "d" => Self::new(minutes, 0).with_delay(Delay::Simple(secs_duration)),
+            _ => Self::new(minutes, 0).with_delay(Delay::Bronstein(secs_duration)),
+        })
+    }
+
+    /// Writes the time control the way `parse` reads it
+    pub fn notation(&self) -> String {
+        let (minutes, kind, secs) = self.parts();
+        format!("{}{}{}", minutes, kind, secs)
+    }
+
+    /// The minutes, the letter `parse` expects for the kind of time added after a
+    /// move, and the seconds added
+    fn parts(&self) -> (u64, char, u64) {
+        let minutes = self.base.as_secs() / 60;
+
+        match self.delay {
+            Delay::None => (minutes, '+', self.increment.as_secs()),
+            Delay::Simple(delay) => (minutes, 'd', delay.as_secs()),
+            Delay::Bronstein(delay) => (minutes, 'b', delay.as_secs()),
+        }
+    }
+}
+
+/// The time control for people to read, like `5 min + 3s`. Only `notation` can be
+/// read back by `parse`
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (minutes, kind, secs) = self.parts();
+
+        match kind {
+            '+' => write!(f, "{} min + {}s", minutes, secs),
+            'd' => write!(f, "{} min, {}s US delay", minutes, secs),
+            _ => write!(f, "{} min, {}s Bronstein delay", minutes, secs),
+        }
+    }
+}
+
+/// A chess clock. Only one side's time runs at once, and pressing the clock
+/// after a move hands over to the other side
+#[derive(Clone, Debug)]
+pub struct Clock {
+    control: Option<TimeControl>,
+    /// The time each side had when its clock was last started, white first
+    remaining: [Duration; 2],
+    running: Option<Side>,
+    /// How long the running side has been thinking about its move
+    spent: Duration,
+}
+
+impl Clock {
+    /// A clock for the time control, or one that never runs out without one
+    pub fn new(control: Option<TimeControl>) -> Self {
+        let base = control.map(|control| control.base).unwrap_or_default();
+
+        Self {
+            control,
+            remaining: [base, base],
+            running: None,
+            spent: Duration::ZERO,
+        }
+    }
+
+    pub fn control(&self) -> Option<TimeControl> {
+        self.control
+    }
+
+    /// Whose time is running, if anyone's
+    pub fn running(&self) -> Option<Side> {
+        self.running
+    }
+
+    /// The time `side` has left, or `None` when the game is not timed
+    pub fn time_left(&self, side: Side) -> Option<Duration> {
+        let control = self.control?;
+        let remaining = self.remaining[index(side)];
+
+        if self.running != Some(side) {
+            return Some(remaining);
+        }
+
+        let used = match control.delay {
+            Delay::Simple(delay) => self.spent.saturating_sub(delay),
+            _ => self.spent,
+        };
+        Some(remaining.saturating_sub(used))
+    }
+
+    /// Starts the time of `side`, or stops the clock with `None`. Nobody gets any
+    /// time added, unlike when the clock is pressed after a move
+    pub fn run(&mut self, side: Option<Side>) {
+        if self.running == side {
+            return;
+        }
+
+        if let Some(running) = self.running {
+            self.remaining[index(running)] = self.time_left(running).unwrap_or_default();
+        }
+        self.running = side;
+        self.spent = Duration::ZERO;
+    }
+
+    /// Called when `side` has made its move. Adds its increment or delay and starts
+    /// the other side's time. The first move is played before any clock runs, and
+    /// still earns its increment
+    pub fn press(&mut self, side: Side) {
+        let Some(control) = self.control else {
+            return;
+        };
+        if self.running == Some(side.opposite()) {
+            return;
+        }
+
+        let refund = match control.delay {
+            Delay::Bronstein(delay) => self.spent.min(delay),
+            _ => Duration::ZERO,
+        };
+        let time_left = self.time_left(side).unwrap_or_default();
+
+        self.remaining[index(side)] = time_left + refund + control.increment;
+        self.running = Some(side.opposite());
+        self.spent = Duration::ZERO;
+    }
+
+    /// Copies the time control and the time each side has left from a clock kept
+    /// somewhere else, like the host's in a network game, along with whose time is
+    /// running. It starts counting from now
+    pub fn follow(
+        &mut self,
+        control: Option<TimeControl>,
+        white: Duration,
+        black: Duration,
+        running: Option<Side>,
+    ) {
+        if self.control != control {
+            *self = Self::new(control);
+        }
+
+        self.remaining = [white, black];
+        self.running = running;
+        self.spent = Duration::ZERO;
+    }
+
+    /// Lets time pass on the running clock. Returns the side whose time ran out,
+    /// which stops the clock
+    pub fn tick(&mut self, elapsed: Duration) -> Option<Side> {
+        let side = self.running?;
+        self.spent += elapsed;
+
+        if self.time_left(side)?.is_zero() {
+            self.remaining[index(side)] = Duration::ZERO;
+            self.running = None;
+            self.spent = Duration::ZERO;
+            return Some(side);
+        }
+
+        None
+    }
+
+    /// How long `side` should think about its move so it does not run out of time,
+    /// at most `limit`
+    pub fn think_time(&self, side: Side, limit: Duration) -> Duration {
+        let (Some(control), Some(time_left)) = (self.control, self.time_left(side)) else {
+            return limit;
+        };
+
+        let bonus = match control.delay {
+            Delay::None => control.increment,
+            Delay::Simple(delay) | Delay::Bronstein(delay) => delay,
+        };
+
+        // plan for another 30 moves and keep most of the bonus as a safety margin
+        let budget = time_left / 30 + bonus / 2;
+        budget.min(limit).min(time_left / 2)
+    }
+}
+
+fn index(side: Side) -> usize {
+    match side {
+        Side::White => 0,
+        Side::Black => 1,
+    }
+}
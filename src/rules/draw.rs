@@ -0,0 +1,139 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::{GameStatus, PieceType, Position};
+
+/// Why a game is drawn, or why a draw can be claimed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrawReason {
+    /// 50 moves each without a capture or pawn move, can be claimed
+    FiftyMoves,
+    /// 75 moves each without a capture or pawn move, ends the game
+    SeventyFiveMoves,
+    /// The same position three times, can be claimed
+    ThreefoldRepetition,
+    /// The same position five times, ends the game
+    FivefoldRepetition,
+    /// Neither side has enough pieces left to checkmate
+    InsufficientMaterial,
+    /// A side ran out of time, but the other side could never checkmate
+    TimeoutVsInsufficientMaterial,
+}
+
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            DrawReason::FiftyMoves => "the fifty move rule",
+            DrawReason::SeventyFiveMoves => "the seventy five move rule",
+            DrawReason::ThreefoldRepetition => "threefold repetition",
+            DrawReason::FivefoldRepetition => "fivefold repetition",
+            DrawReason::InsufficientMaterial => "insufficient material",
+            DrawReason::TimeoutVsInsufficientMaterial => {
+                "running out of time against insufficient material"
+            }
+        };
+
+        write!(f, "{}", text)
+    }
+}
+
+impl Position {
+    /// Positions with the same key are the same for the repetition rules: the same
+    /// pieces on the same squares, side to move, castling rights and en passant
+    /// square, if a pawn can take en passant
+    pub(super) fn repetition_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for piece in self.pieces() {
+            (piece.piece_type, piece.side, piece.board_pos).hash(&mut hasher);
+        }
+        self.turn.hash(&mut hasher);
+        self.castling_fen().hash(&mut hasher);
+
+        // the square behind the pawn is empty, so the only move onto it is en passant
+        let en_passant = self.en_passant_square().filter(|&square| {
+            self.get_all_possible_moves().iter().any(|change| {
+                let (start, end) = change.mover_squares();
+                end == square && self.get_piece(start).unwrap().piece_type == PieceType::Pawn
+            })
+        });
+        en_passant.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// How many times the current position has come up, counting this time
+    pub fn repetitions(&self) -> usize {
+        let key = self.repetition_key();
+
+        // a capture or pawn move means no earlier position can come back
+        let repeated = self
+            .previous
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .filter(|snapshot| snapshot.key == key)
+            .count();
+
+        repeated + 1
+    }
+
+    /// True when no sequence of moves could end in checkmate: only kings, or
+    /// a single knight or bishop, or only bishops all on the same colour squares
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut minor_pieces = 0;
+        let mut bishop_colours = [false; 2];
+
+        for piece in self.pieces() {
+            match piece.piece_type {
+                PieceType::King => (),
+                PieceType::Knight => minor_pieces += 1,
+                PieceType::Bishop => {
+                    minor_pieces += 1;
+                    bishop_colours[(piece.board_pos.x + piece.board_pos.y) % 2] = true;
+                }
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen => return false,
+            }
+        }
+
+        let only_bishops_on_one_colour = bishop_colours.iter().filter(|&&c| c).count() == 1
+            && self
+                .pieces()
+                .all(|piece| piece.piece_type != PieceType::Knight);
+
+        minor_pieces <= 1 || only_bishops_on_one_colour
+    }
+
+    /// A draw the side to move could claim, if any
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetitions() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoves)
+        } else {
+            None
+        }
+    }
+
+    /// Ends the game in a draw if one can be claimed
+    pub fn claim_draw(&mut self) -> Option<DrawReason> {
+        let reason = self.claimable_draw()?;
+        self.ended = Some(GameStatus::Draw { reason });
+
+        Some(reason)
+    }
+
+    /// A draw that ends the game without anyone claiming it
+    pub(super) fn automatic_draw(&self) -> Option<DrawReason> {
+        if self.has_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.repetitions() >= 5 {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.halfmove_clock >= 150 {
+            Some(DrawReason::SeventyFiveMoves)
+        } else {
+            None
+        }
+    }
+}
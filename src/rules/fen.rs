@@ -0,0 +1,392 @@
+use super::{BoardPos, Piece, PieceType, Position, Side, Variant, BOARD_HEIGHT, BOARD_WIDTH};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+}
+
+/// The fields of a FEN string, before they are turned into a position
+#[derive(Clone, Debug)]
+pub struct FenPosition {
+    pub pieces: Vec<(PieceType, Side, BoardPos)>,
+    pub turn: Side,
+    pub castling: CastlingRights,
+    pub en_passant: Option<BoardPos>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl PieceType {
+    /// The letter used for the piece in FEN and SAN, always upper case
+    pub fn letter(&self) -> char {
+        match self {
+            PieceType::King => 'K',
+            PieceType::Queen => 'Q',
+            PieceType::Rook => 'R',
+            PieceType::Bishop => 'B',
+            PieceType::Knight => 'N',
+            PieceType::Pawn => 'P',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'K' => Some(PieceType::King),
+            'Q' => Some(PieceType::Queen),
+            'R' => Some(PieceType::Rook),
+            'B' => Some(PieceType::Bishop),
+            'N' => Some(PieceType::Knight),
+            'P' => Some(PieceType::Pawn),
+            _ => None,
+        }
+    }
+}
+
+impl FenPosition {
+    pub fn parse(fen: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(format!(
+                "Expected 4 to 6 fields in FEN, found {}",
+                fields.len()
+            ));
+        }
+
+        let pieces = Self::parse_placement(fields[0])?;
+
+        let turn = match fields[1] {
+            "w" => Side::White,
+            "b" => Side::Black,
+            other => return Err(format!("Invalid side to move: {}", other)),
+        };
+
+        let mut castling = CastlingRights::default();
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                match c {
+                    'K' => castling.white_king_side = true,
+                    'Q' => castling.white_queen_side = true,
+                    'k' => castling.black_king_side = true,
+                    'q' => castling.black_queen_side = true,
+                    _ => return Err(format!("Invalid castling rights: {}", fields[2])),
+                }
+            }
+        }
+
+        let en_passant = match fields[3] {
+            "-" => None,
+            square => {
+                let pos = BoardPos::from_algebraic(square)?;
+                // the square is just behind a pawn that the other side has moved two squares
+                let (rank, pawn_rank) = match turn {
+                    Side::White => (BOARD_HEIGHT - 3, BOARD_HEIGHT - 4),
+                    Side::Black => (2, 3),
+                };
+                if pos.y != rank {
+                    return Err(format!("Invalid en passant square: {}", square));
+                }
+
+                let pawn = (
+                    PieceType::Pawn,
+                    turn.opposite(),
+                    BoardPos::new(pos.x, pawn_rank)?,
+                );
+                if !pieces.contains(&pawn) {
+                    return Err(format!("No pawn to take en passant on {}", square));
+                }
+                Some(pos)
+            }
+        };
+
+        let parse_counter = |index: usize, default: u32| -> Result<u32, String> {
+            match fields.get(index) {
+                Some(field) => field
+                    .parse()
+                    .map_err(|_| format!("Invalid move counter: {}", field)),
+                None => Ok(default),
+            }
+        };
+
+        Ok(Self {
+            pieces,
+            turn,
+            castling,
+            en_passant,
+            halfmove_clock: parse_counter(4, 0)?,
+            fullmove_number: parse_counter(5, 1)?.max(1),
+        })
+    }
+
+    fn parse_placement(placement: &str) -> Result<Vec<(PieceType, Side, BoardPos)>, String> {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != BOARD_HEIGHT {
+            return Err(format!(
+                "Expected {} ranks in FEN, found {}",
+                BOARD_HEIGHT,
+                ranks.len()
+            ));
+        }
+
+        let mut pieces = Vec::new();
+        // the first rank in a FEN is the top of the board
+        for (i, rank) in ranks.iter().enumerate() {
+            let y = BOARD_HEIGHT - 1 - i;
+            let mut x = 0;
+            let wrong_length = || format!("Rank {} does not have {} squares", y + 1, BOARD_WIDTH);
+
+            for c in rank.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    x += empty as usize;
+                    continue;
+                }
+
+                let piece_type =
+                    PieceType::from_letter(c).ok_or(format!("Invalid piece in FEN: {}", c))?;
+                let side = if c.is_ascii_uppercase() {
+                    Side::White
+                } else {
+                    Side::Black
+                };
+
+                if x >= BOARD_WIDTH {
+                    return Err(wrong_length());
+                }
+                pieces.push((piece_type, side, BoardPos::new(x, y)?));
+                x += 1;
+            }
+
+            if x != BOARD_WIDTH {
+                return Err(wrong_length());
+            }
+        }
+
+        for side in [Side::White, Side::Black] {
+            let kings = pieces
+                .iter()
+                .filter(|(piece_type, s, _)| *piece_type == PieceType::King && *s == side)
+                .count();
+
+            if kings != 1 {
+                return Err(format!("Expected one {:?} king, found {}", side, kings));
+            }
+        }
+
+        Ok(pieces)
+    }
+
+    /// The half move count the board uses, starting at 0 for white's first move
+    pub fn turn_num(&self) -> u32 {
+        let black_to_move = match self.turn {
+            Side::White => 0,
+            Side::Black => 1,
+        };
+
+        (self.fullmove_number - 1) * 2 + black_to_move
+    }
+
+    /// Castling and double pawn moves are only allowed for pieces that have not
+    /// moved, so this works out which pieces have to be marked as moved
+    pub fn has_moved(&self, piece_type: PieceType, side: Side, board_pos: BoardPos) -> bool {
+        let (back_rank, pawn_rank, king_side, queen_side) = match side {
+            Side::White => (
+                0,
+                1,
+                self.castling.white_king_side,
+                self.castling.white_queen_side,
+            ),
+            Side::Black => (
+                BOARD_HEIGHT - 1,
+                BOARD_HEIGHT - 2,
+                self.castling.black_king_side,
+                self.castling.black_queen_side,
+            ),
+        };
+
+        match piece_type {
+            PieceType::Pawn => board_pos.y != pawn_rank,
+            PieceType::King => {
+                board_pos != BoardPos::new(4, back_rank).unwrap() || !(king_side || queen_side)
+            }
+            PieceType::Rook => {
+                let king_side_rook = BoardPos::new(BOARD_WIDTH - 1, back_rank).unwrap();
+                let queen_side_rook = BoardPos::new(0, back_rank).unwrap();
+
+                !(board_pos == king_side_rook && king_side
+                    || board_pos == queen_side_rook && queen_side)
+            }
+            _ => false,
+        }
+    }
+
+    /// The square of the pawn that can be captured en passant
+    pub fn en_passant_pawn(&self) -> Option<BoardPos> {
+        let square = self.en_passant?;
+        let y = match square.y {
+            2 => 3,
+            _ => BOARD_HEIGHT - 4,
+        };
+
+        BoardPos::new(square.x, y).ok()
+    }
+}
+
+impl Position {
+    pub fn from_fen(fen: &str) -> Result<Self, String> {
+        let fen_position = FenPosition::parse(fen)?;
+
+        let mut position = Self {
+            board: vec![vec![None; BOARD_HEIGHT]; BOARD_WIDTH],
+            turn: fen_position.turn,
+            turn_num: fen_position.turn_num(),
+            halfmove_clock: fen_position.halfmove_clock,
+            previous: Vec::new(),
+            history: Vec::new(),
+            undone: Vec::new(),
+            start_fen: String::new(),
+            variant: Variant::default(),
+            ended: None,
+        };
+
+        for &(piece_type, side, board_pos) in fen_position.pieces.iter() {
+            let num_moves = match fen_position.has_moved(piece_type, side, board_pos) {
+                true => 1,
+                false => 0,
+            };
+
+            let piece = Piece {
+                piece_type,
+                side,
+                board_pos,
+                distance_moved: 0,
+                num_moves,
+                turn_last_moved: 0,
+            };
+            position.set_piece(Some(piece), board_pos);
+        }
+
+        // mark the pawn that just moved two squares so it can be taken en passant
+        if let Some(pawn_square) = fen_position.en_passant_pawn() {
+            if let Some(mut pawn) = position.get_piece(pawn_square) {
+                pawn.num_moves = 1;
+                pawn.distance_moved = 2;
+                pawn.turn_last_moved = position.turn_num.saturating_sub(1);
+                position.set_piece(Some(pawn), pawn_square);
+            }
+        }
+
+        position.start_fen = position.to_fen();
+        Ok(position)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for y in (0..BOARD_HEIGHT).rev() {
+            let mut empty = 0;
+            for x in 0..BOARD_WIDTH {
+                match &self.board[x][y] {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+
+                        let letter = piece.piece_type.letter();
+                        placement.push(match piece.side {
+                            Side::White => letter,
+                            Side::Black => letter.to_ascii_lowercase(),
+                        });
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if y > 0 {
+                placement.push('/');
+            }
+        }
+
+        let turn = match self.turn {
+            Side::White => "w",
+            Side::Black => "b",
+        };
+
+        let en_passant = match self.en_passant_square() {
+            Some(square) => square.to_algebraic(),
+            None => "-".to_owned(),
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            turn,
+            self.castling_fen(),
+            en_passant,
+            self.halfmove_clock,
+            self.turn_num / 2 + 1
+        )
+    }
+
+    pub(super) fn castling_fen(&self) -> String {
+        let unmoved = |x: usize, y: usize, piece_type: PieceType, side: Side| -> bool {
+            match self.get_piece(BoardPos::new(x, y).unwrap()) {
+                Some(piece) => {
+                    piece.piece_type == piece_type && piece.side == side && piece.num_moves == 0
+                }
+                None => false,
+            }
+        };
+
+        let mut castling = String::new();
+        for (side, y, king_side, queen_side) in [
+            (Side::White, 0, 'K', 'Q'),
+            (Side::Black, BOARD_HEIGHT - 1, 'k', 'q'),
+        ] {
+            if !unmoved(4, y, PieceType::King, side) {
+                continue;
+            }
+            if unmoved(BOARD_WIDTH - 1, y, PieceType::Rook, side) {
+                castling.push(king_side);
+            }
+            if unmoved(0, y, PieceType::Rook, side) {
+                castling.push(queen_side);
+            }
+        }
+
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        castling
+    }
+
+    /// The square behind a pawn that has just moved two squares
+    pub(super) fn en_passant_square(&self) -> Option<BoardPos> {
+        let (pawn_rank, behind) = match self.turn {
+            Side::White => (BOARD_HEIGHT - 4, BOARD_HEIGHT - 3),
+            Side::Black => (3, 2),
+        };
+
+        (0..BOARD_WIDTH).find_map(|x| {
+            let piece = self.get_piece(BoardPos::new(x, pawn_rank).unwrap())?;
+
+            if piece.piece_type == PieceType::Pawn
+                && piece.side.is_enemy(&self.turn)
+                && piece.num_moves == 1
+                && piece.distance_moved == 2
+                && piece.turns_since_last_move(self.turn_num) == 1
+            {
+                BoardPos::new(x, behind).ok()
+            } else {
+                None
+            }
+        })
+    }
+}
@@ -1,11 +1,27 @@
-use crate::constants::BOARD_HEIGHT;
-
 use super::{
     BChange,
     BChange::{BothMove, Move, MoveDestroy},
-    Board, BoardPos, Piece, PieceType, Side,
+    BoardPos, DrawReason, Piece, PieceType, Pieces, Position, Side, Variant, BOARD_HEIGHT,
+    PROMOTION_TYPES,
 };
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameStatus {
+    Ongoing,
+    Check,
+    Checkmate { winner: Side },
+    Stalemate,
+    Draw { reason: DrawReason },
+    Resignation { winner: Side },
+    Timeout { winner: Side },
+}
+
+impl GameStatus {
+    pub fn is_over(&self) -> bool {
+        !matches!(self, GameStatus::Ongoing | GameStatus::Check)
+    }
+}
+
 #[derive(Clone, Copy)]
 enum Dir {
     Up,
@@ -26,10 +42,7 @@ impl BoardPos {
                 return None;
             }
             let (x, y) = (x as usize, y as usize);
-            match BoardPos::new(x, y) {
-                Ok(pos) => Some(pos),
-                Err(_) => None,
-            }
+            BoardPos::new(x, y).ok()
         };
 
         let x = self.x as i32;
@@ -48,7 +61,7 @@ impl BoardPos {
     }
 }
 
-fn slide(dir: Dir, side: Side, start: BoardPos, board: &Board) -> Vec<BChange> {
+fn slide(dir: Dir, side: Side, start: BoardPos, position: &Position) -> Vec<BChange> {
     let mut moves = Vec::new();
     let mut current_square = start;
     loop {
@@ -57,7 +70,7 @@ fn slide(dir: Dir, side: Side, start: BoardPos, board: &Board) -> Vec<BChange> {
             None => break,
         };
 
-        match board.get_piece(current_square) {
+        match position.get_piece(current_square) {
             Some(target_piece) => {
                 if side.is_enemy(&target_piece.side) {
                     let board_change = MoveDestroy {
@@ -79,18 +92,257 @@ fn slide(dir: Dir, side: Side, start: BoardPos, board: &Board) -> Vec<BChange> {
     moves
 }
 
-impl Board {
-    pub fn check_valid_change(&self, start: BoardPos, click_pos: BoardPos) -> Option<BChange> {
-        for change in self.get_possible_moves(start) {
-            if change.click_pos_to_activate_change() == click_pos {
-                return Some(change);
+/// Plays the change on a copy of the pieces without touching any entities,
+/// used to look ahead when filtering out illegal moves
+fn simulate_change(pieces: &mut Pieces, change: &BChange) {
+    fn move_piece(pieces: &mut Pieces, start: BoardPos, end: BoardPos) {
+        if let Some(mut piece) = pieces[start.x][start.y].take() {
+            piece.board_pos = end;
+            pieces[end.x][end.y] = Some(piece);
+        }
+    }
+
+    match *change {
+        Move { start, end } => move_piece(pieces, start, end),
+        MoveDestroy { start, end, target } => {
+            pieces[target.x][target.y] = None;
+            move_piece(pieces, start, end);
+        }
+        BothMove {
+            start1,
+            start2,
+            end1,
+            end2,
+        } => {
+            let piece1 = pieces[start1.x][start1.y].take();
+            let piece2 = pieces[start2.x][start2.y].take();
+            pieces[end1.x][end1.y] = piece1;
+            pieces[end2.x][end2.y] = piece2;
+        }
+        BChange::PushPremote {
+            start1,
+            start2,
+            end1,
+            end2,
+            piece_type,
+        } => {
+            let piece1 = pieces[start1.x][start1.y].take();
+            let mut piece2 = pieces[start2.x][start2.y].take();
+            if let Some(piece2) = &mut piece2 {
+                piece2.piece_type = piece_type;
+            }
+            pieces[end1.x][end1.y] = piece1;
+            pieces[end2.x][end2.y] = piece2;
+        }
+        BChange::Promotion {
+            start,
+            end,
+            piece_type,
+        } => {
+            pieces[end.x][end.y] = None;
+            move_piece(pieces, start, end);
+            if let Some(piece) = &mut pieces[end.x][end.y] {
+                piece.piece_type = piece_type;
             }
         }
+    }
+}
+
+fn find_king(pieces: &Pieces, side: Side) -> Option<BoardPos> {
+    pieces.iter().flatten().flatten().find_map(|piece| {
+        if piece.piece_type == PieceType::King && piece.side.is_friendly(&side) {
+            Some(piece.board_pos)
+        } else {
+            None
+        }
+    })
+}
+
+/// Checks if any piece of the `by` side could capture on the square
+fn is_attacked(pieces: &Pieces, square: BoardPos, by: Side) -> bool {
+    let is_attacker = |pos: Option<BoardPos>, types: &[PieceType]| -> bool {
+        let Some(pos) = pos else {
+            return false;
+        };
 
-        None
+        match &pieces[pos.x][pos.y] {
+            Some(piece) => piece.side.is_friendly(&by) && types.contains(&piece.piece_type),
+            None => false,
+        }
+    };
+
+    let knight_dirs = [
+        Dir::Custom(1, 2),
+        Dir::Custom(2, 1),
+        Dir::Custom(1, -2),
+        Dir::Custom(2, -1),
+        Dir::Custom(-1, 2),
+        Dir::Custom(-2, 1),
+        Dir::Custom(-1, -2),
+        Dir::Custom(-2, -1),
+    ];
+
+    for dir in knight_dirs {
+        if is_attacker(square.square_in_dir(dir), &[PieceType::Knight]) {
+            return true;
+        }
+    }
+
+    // pawns attack diagonally forward, so look backwards from the square
+    let pawn_dirs = match by {
+        Side::White => [Dir::DownLeft, Dir::DownRight],
+        Side::Black => [Dir::UpLeft, Dir::UpRight],
+    };
+
+    for dir in pawn_dirs {
+        if is_attacker(square.square_in_dir(dir), &[PieceType::Pawn]) {
+            return true;
+        }
+    }
+
+    let straight_dirs = [Dir::Up, Dir::Down, Dir::Left, Dir::Right];
+    let diagonal_dirs = [Dir::UpRight, Dir::UpLeft, Dir::DownRight, Dir::DownLeft];
+
+    for dir in straight_dirs.into_iter().chain(diagonal_dirs) {
+        if is_attacker(square.square_in_dir(dir), &[PieceType::King]) {
+            return true;
+        }
+    }
+
+    let check_slide = |dir: Dir, types: &[PieceType]| -> bool {
+        let mut current_square = square;
+        while let Some(next_square) = current_square.square_in_dir(dir) {
+            if pieces[next_square.x][next_square.y].is_some() {
+                return is_attacker(Some(next_square), types);
+            }
+            current_square = next_square;
+        }
+
+        false
+    };
+
+    for dir in straight_dirs {
+        if check_slide(dir, &[PieceType::Rook, PieceType::Queen]) {
+            return true;
+        }
+    }
+
+    for dir in diagonal_dirs {
+        if check_slide(dir, &[PieceType::Bishop, PieceType::Queen]) {
+            return true;
+        }
+    }
+
+    false
+}
+
+impl Position {
+    pub fn check_valid_change(&self, start: BoardPos, click_pos: BoardPos) -> Option<BChange> {
+        self.get_possible_moves(start)
+            .into_iter()
+            .find(|change| change.click_pos_to_activate_change() == click_pos)
     }
 
+    /// Only returns the moves that do not leave the king of the mover in check
     pub fn get_possible_moves(&self, selected_square: BoardPos) -> Vec<BChange> {
+        let side = self.get_piece(selected_square).unwrap().side;
+
+        self.get_pseudo_legal_moves(selected_square)
+            .into_iter()
+            .filter(|change| !self.leaves_king_in_check(change, side))
+            .collect()
+    }
+
+    pub fn get_all_possible_moves(&self) -> Vec<BChange> {
+        let mut moves = Vec::new();
+        for piece in self.board.iter().flatten().flatten() {
+            if self.is_turn(piece.side) {
+                moves.extend(self.get_possible_moves(piece.board_pos));
+            }
+        }
+
+        moves
+    }
+
+    pub fn is_square_attacked(&self, square: BoardPos, by: Side) -> bool {
+        is_attacked(&self.board, square, by)
+    }
+
+    pub fn is_in_check(&self, side: Side) -> bool {
+        match find_king(&self.board, side) {
+            Some(king_square) => self.is_square_attacked(king_square, side.opposite()),
+            None => false,
+        }
+    }
+
+    /// The square of the king of the side to move, if it is in check
+    pub fn checked_king(&self) -> Option<BoardPos> {
+        let king_square = find_king(&self.board, self.turn)?;
+        self.is_square_attacked(king_square, self.turn.opposite())
+            .then_some(king_square)
+    }
+
+    pub fn game_status(&self) -> GameStatus {
+        if let Some(status) = self.ended {
+            return status;
+        }
+
+        let in_check = self.is_in_check(self.turn);
+        let has_moves = !self.get_all_possible_moves().is_empty();
+
+        match (in_check, has_moves) {
+            (true, false) => GameStatus::Checkmate {
+                winner: self.turn.opposite(),
+            },
+            (false, false) => GameStatus::Stalemate,
+            _ => match self.automatic_draw() {
+                Some(reason) => GameStatus::Draw { reason },
+                None if in_check => GameStatus::Check,
+                None => GameStatus::Ongoing,
+            },
+        }
+    }
+
+    pub fn resign(&mut self, side: Side) {
+        self.ended = Some(GameStatus::Resignation {
+            winner: side.opposite(),
+        });
+    }
+
+    /// Ends the game because `side` ran out of time. It is only a loss if the other
+    /// side still has enough pieces to checkmate
+    pub fn time_out(&mut self, side: Side) {
+        let winner = side.opposite();
+        let mut minor_pieces = 0;
+        let mut can_mate = false;
+
+        for piece in self.pieces().filter(|piece| piece.side == winner) {
+            match piece.piece_type {
+                PieceType::King => (),
+                PieceType::Knight | PieceType::Bishop => minor_pieces += 1,
+                _ => can_mate = true,
+            }
+        }
+
+        self.ended = Some(match can_mate || minor_pieces > 1 {
+            true => GameStatus::Timeout { winner },
+            false => GameStatus::Draw {
+                reason: DrawReason::TimeoutVsInsufficientMaterial,
+            },
+        });
+    }
+
+    fn leaves_king_in_check(&self, change: &BChange, side: Side) -> bool {
+        let mut pieces = self.board.clone();
+        simulate_change(&mut pieces, change);
+
+        match find_king(&pieces, side) {
+            Some(king_square) => is_attacked(&pieces, king_square, side.opposite()),
+            None => false,
+        }
+    }
+
+    fn get_pseudo_legal_moves(&self, selected_square: BoardPos) -> Vec<BChange> {
         let piece = self.get_piece(selected_square).unwrap(); // if this fails we have a bug
 
         // not the correct turn then no moves
@@ -138,10 +390,19 @@ impl Board {
         let check_castling = || -> Vec<BChange> {
             let mut changes = Vec::new();
 
-            if piece.num_moves > 0 {
+            if piece.num_moves > 0 || self.is_square_attacked(selected_square, side.opposite()) {
                 return changes;
             }
 
+            // the king can not pass through or land on an attacked square
+            let path_is_safe = |dir: Dir| -> bool {
+                let transit = selected_square.square_in_dir(dir).unwrap();
+                let destination = transit.square_in_dir(dir).unwrap();
+
+                !self.is_square_attacked(transit, side.opposite())
+                    && !self.is_square_attacked(destination, side.opposite())
+            };
+
             // Check if the king can castle to the right
             let mut open_squares = true;
             for x in 1..3 {
@@ -156,7 +417,7 @@ impl Board {
                 }
             }
 
-            if open_squares {
+            if open_squares && path_is_safe(Dir::Right) {
                 let rook_square = selected_square.square_in_dir(Dir::Custom(3, 0)).unwrap();
 
                 if let Some(rook) = self.get_piece(rook_square) {
@@ -185,7 +446,7 @@ impl Board {
                 }
             }
 
-            if open_squares {
+            if open_squares && path_is_safe(Dir::Left) {
                 let rook_square = selected_square.square_in_dir(Dir::Custom(-4, 0)).unwrap();
 
                 if let Some(rook) = self.get_piece(rook_square) {
@@ -262,15 +523,8 @@ impl Board {
                 return None;
             }
 
-            let one_square = match selected_square.square_in_dir(dir) {
-                Some(square) => square,
-                None => return None,
-            };
-
-            let two_square = match one_square.square_in_dir(dir) {
-                Some(square) => square,
-                None => return None,
-            };
+            let one_square = selected_square.square_in_dir(dir)?;
+            let two_square = one_square.square_in_dir(dir)?;
 
             if self.is_occupied(one_square) || self.is_occupied(two_square) {
                 return None;
@@ -310,7 +564,10 @@ impl Board {
 
                 // check if the pawn can be promoted
                 if piece.board_pos.y == BOARD_HEIGHT - 2 {
-                    return moves.iter().map(|f| f.convert_to_promotion()).collect();
+                    return moves
+                        .iter()
+                        .flat_map(|f| PROMOTION_TYPES.map(|t| f.convert_to_promotion(t)))
+                        .collect();
                 }
 
                 if let Some(change) = check_double_space_move(Dir::Up) {
@@ -343,7 +600,10 @@ impl Board {
 
                 // check if the pawn can be promoted
                 if piece.board_pos.y == 1 {
-                    return moves.iter().map(|f| f.convert_to_promotion()).collect();
+                    return moves
+                        .iter()
+                        .flat_map(|f| PROMOTION_TYPES.map(|t| f.convert_to_promotion(t)))
+                        .collect();
                 }
 
                 if let Some(change) = check_double_space_move(Dir::Down) {
@@ -409,7 +669,12 @@ impl Board {
             };
 
             // this means that it has to be an enemy
-            if self.is_occupied(last_square) && slide_moves.len() > 0 {
+            if self.is_occupied(last_square) && !slide_moves.is_empty() {
+                moves.extend(slide_moves);
+                return;
+            }
+
+            if self.variant != Variant::RookPush {
                 moves.extend(slide_moves);
                 return;
             }
@@ -418,7 +683,7 @@ impl Board {
                 if self.is_occupied_and_friendly(next_square, piece.side) {
                     if let Some(push_move) = next_square.square_in_dir(dir) {
                         if !self.is_occupied(push_move) {
-                            let mut new_move = BothMove {
+                            let new_move = BothMove {
                                 start1: selected_square,
                                 start2: next_square,
                                 end1: next_square,
@@ -429,25 +694,24 @@ impl Board {
                                 panic!("This should not panic")
                             };
 
-                            if pushed_piece.piece_type == PieceType::Pawn {
-                                dbg!(push_move.y);
-                                match pushed_piece.side {
+                            let promotes = pushed_piece.piece_type == PieceType::Pawn
+                                && match pushed_piece.side {
                                     Side::White => {
-                                        if pushed_piece.board_pos.y == BOARD_HEIGHT - 2
+                                        pushed_piece.board_pos.y == BOARD_HEIGHT - 2
                                             && push_move.y == BOARD_HEIGHT - 1
-                                        {
-                                            new_move = new_move.convert_to_promotion();
-                                        }
                                     }
                                     Side::Black => {
-                                        if pushed_piece.board_pos.y == 1 && push_move.y == 0 {
-                                            new_move = new_move.convert_to_promotion();
-                                        }
+                                        pushed_piece.board_pos.y == 1 && push_move.y == 0
                                     }
-                                }
+                                };
+
+                            if promotes {
+                                moves.extend(
+                                    PROMOTION_TYPES.map(|t| new_move.convert_to_promotion(t)),
+                                );
+                            } else {
+                                moves.push(new_move);
                             }
-
-                            moves.push(new_move);
                         }
                     }
                 }
@@ -0,0 +1,669 @@
+pub mod clock;
+pub mod draw;
+pub mod fen;
+pub mod logic;
+pub mod perft;
+pub mod pgn;
+pub mod san;
+pub mod uci;
+
+pub use clock::{Clock, TimeControl};
+pub use draw::DrawReason;
+pub use fen::START_FEN;
+pub use logic::GameStatus;
+
+pub const BOARD_WIDTH: usize = 8;
+pub const BOARD_HEIGHT: usize = 8;
+
+/// The state of a game without anything needed to draw it
+#[derive(Clone)]
+pub struct Position {
+    board: Pieces,
+    turn: Side,
+    turn_num: u32,
+    halfmove_clock: u32,
+    previous: Vec<Snapshot>,
+    history: Vec<MoveRecord>,
+    /// Changes taken back with `undo`, the last one is redone first
+    undone: Vec<BChange>,
+    start_fen: String,
+    variant: Variant,
+    /// How the game ended when it was not decided on the board, like a resignation
+    ended: Option<GameStatus>,
+}
+
+type Pieces = Vec<Vec<Option<Piece>>>;
+
+/// Everything needed to go back to the position before a change
+#[derive(Clone)]
+struct Snapshot {
+    /// What was on each square the change touched before it was made
+    squares: Vec<(BoardPos, Option<Piece>)>,
+    /// What the change did to the pieces, so it can be played backwards
+    diffs: Vec<PieceDiff>,
+    turn_num: u32,
+    halfmove_clock: u32,
+    /// Identifies the position for spotting repetitions
+    key: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BoardPos {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// A change that was applied to the board, with how it is written in SAN
+#[derive(Clone, Debug)]
+pub struct MoveRecord {
+    pub change: BChange,
+    pub san: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BChange {
+    Move {
+        start: BoardPos,
+        end: BoardPos,
+    },
+    MoveDestroy {
+        start: BoardPos,
+        end: BoardPos,
+        target: BoardPos,
+    },
+    BothMove {
+        start1: BoardPos,
+        start2: BoardPos,
+        end1: BoardPos,
+        end2: BoardPos,
+    },
+    Promotion {
+        start: BoardPos,
+        end: BoardPos,
+        piece_type: PieceType,
+    },
+    PushPremote {
+        start1: BoardPos,
+        start2: BoardPos,
+        end1: BoardPos,
+        end2: BoardPos,
+        piece_type: PieceType,
+    },
+}
+
+/// What happened to a single piece when a change was applied, in the order
+/// it happened, so a frontend can keep its own view of the pieces in sync
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PieceDiff {
+    Moved {
+        from: BoardPos,
+        to: BoardPos,
+    },
+    Removed {
+        at: BoardPos,
+    },
+    Promoted {
+        at: BoardPos,
+        piece_type: PieceType,
+        side: Side,
+    },
+    /// A captured piece coming back when a change is undone
+    Added {
+        at: BoardPos,
+        piece_type: PieceType,
+        side: Side,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PieceType {
+    King,
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+    Pawn,
+}
+
+/// The pieces a pawn can turn into, in the order they are offered to the player
+pub const PROMOTION_TYPES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+/// Which set of rules the pieces follow
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Variant {
+    /// Rooks can push a friendly piece one square along their line
+    #[default]
+    RookPush,
+    /// Normal chess
+    Standard,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Side {
+    White,
+    Black,
+}
+
+#[derive(Clone, Debug)]
+pub struct Piece {
+    pub piece_type: PieceType,
+    pub side: Side,
+    pub board_pos: BoardPos,
+    pub distance_moved: u32,
+    pub num_moves: u32,
+    pub turn_last_moved: u32,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Position {
+    /// The normal starting position
+    pub fn new() -> Self {
+        Self::from_fen(START_FEN).expect("The starting FEN should be valid")
+    }
+
+    fn save(&self, board_change: &BChange) -> Snapshot {
+        Snapshot {
+            squares: board_change
+                .squares()
+                .into_iter()
+                .map(|board_pos| (board_pos, self.get_piece(board_pos)))
+                .collect(),
+            diffs: Vec::new(),
+            turn_num: self.turn_num,
+            halfmove_clock: self.halfmove_clock,
+            key: self.repetition_key(),
+        }
+    }
+
+    fn next_turn(&mut self) {
+        self.turn = self.turn.opposite();
+
+        self.turn_num += 1;
+    }
+
+    fn previous_turn(&mut self, turn_num: u32) {
+        self.turn = self.turn.opposite();
+        self.turn_num = turn_num;
+    }
+
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn turn(&self) -> Side {
+        self.turn
+    }
+
+    pub fn is_turn(&self, side: Side) -> bool {
+        side.is_friendly(&self.turn)
+    }
+
+    /// Half-moves since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
+
+    /// All the pieces on the board
+    pub fn pieces(&self) -> impl Iterator<Item = &Piece> {
+        self.board.iter().flatten().flatten()
+    }
+
+    /// Plays the change and records it in the history. Anything that was undone
+    /// can not be redone after this
+    pub fn apply_change(&mut self, board_change: BChange) -> Vec<PieceDiff> {
+        self.undone.clear();
+        self.record_change(board_change)
+    }
+
+    fn record_change(&mut self, board_change: BChange) -> Vec<PieceDiff> {
+        let mut san = self.san(&board_change);
+        let record_change = board_change.clone();
+        let diffs = self.play(board_change);
+
+        match self.game_status() {
+            GameStatus::Check => san.push('+'),
+            GameStatus::Checkmate { .. } => san.push('#'),
+            _ => (),
+        }
+
+        self.history.push(MoveRecord {
+            change: record_change,
+            san,
+        });
+
+        diffs
+    }
+
+    /// Applies a change without recording it in the history, which is a lot
+    /// cheaper when searching. It has to be taken back with `unmake_change`
+    pub fn make_change(&mut self, board_change: BChange) {
+        self.play(board_change);
+    }
+
+    /// Takes back a change made with `make_change`
+    pub fn unmake_change(&mut self) {
+        self.take_back();
+    }
+
+    /// Puts back only the squares the last change touched, along with the
+    /// clocks and the metadata of the pieces that moved
+    fn take_back(&mut self) -> Option<Snapshot> {
+        let snapshot = self.previous.pop()?;
+
+        for (board_pos, piece) in snapshot.squares.iter() {
+            self.set_piece(piece.clone(), *board_pos);
+        }
+        self.halfmove_clock = snapshot.halfmove_clock;
+        self.ended = None;
+        self.previous_turn(snapshot.turn_num);
+
+        Some(snapshot)
+    }
+
+    fn play(&mut self, board_change: BChange) -> Vec<PieceDiff> {
+        let mut snapshot = self.save(&board_change);
+
+        // pawn moves and captures can not be undone, so they reset the clock
+        if board_change.is_capture(self) || board_change.moves_pawn(self) {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        let mut diffs = Vec::new();
+        match board_change {
+            BChange::Move { start, end } => {
+                self.move_piece(start, end, &mut diffs);
+            }
+            BChange::MoveDestroy { start, end, target } => {
+                self.take(target).unwrap();
+                diffs.push(PieceDiff::Removed { at: target });
+                self.move_piece(start, end, &mut diffs);
+            }
+            BChange::BothMove {
+                start1,
+                start2,
+                end1,
+                end2,
+            } => {
+                self.move_both_pieces(start1, start2, end1, end2, &mut diffs);
+            }
+            BChange::Promotion {
+                start,
+                end,
+                piece_type,
+            } => {
+                self.promote_piece(start, piece_type, &mut diffs);
+
+                if self.take(end).is_some() {
+                    diffs.push(PieceDiff::Removed { at: end });
+                }
+
+                self.move_piece(start, end, &mut diffs);
+            }
+            BChange::PushPremote {
+                start1,
+                start2,
+                end1,
+                end2,
+                piece_type,
+            } => {
+                self.promote_piece(start2, piece_type, &mut diffs);
+                self.move_both_pieces(start1, start2, end1, end2, &mut diffs)
+            }
+        }
+
+        // finally change the turn
+        self.next_turn();
+
+        snapshot.diffs = diffs.clone();
+        self.previous.push(snapshot);
+
+        diffs
+    }
+
+    /// Goes back to the position before the last change, returns the diffs that
+    /// take it back or `None` if there was nothing to undo
+    pub fn undo(&mut self) -> Option<Vec<PieceDiff>> {
+        let record = self.history.pop()?;
+        let snapshot = self.take_back()?;
+        self.undone.push(record.change);
+
+        let before = |at: BoardPos| {
+            let (_, piece) = snapshot.squares.iter().find(|(pos, _)| *pos == at)?;
+            piece.as_ref()
+        };
+
+        // play the diffs backwards, so pieces move back to where they came from
+        let mut diffs = Vec::new();
+        for diff in snapshot.diffs.iter().rev() {
+            match *diff {
+                PieceDiff::Moved { from, to } => {
+                    // a promoted piece turns back into a pawn first, so the pawn
+                    // is what moves back
+                    let promoted = snapshot
+                        .diffs
+                        .iter()
+                        .any(|diff| matches!(diff, PieceDiff::Promoted { at, .. } if *at == from));
+                    if let (true, Some(pawn)) = (promoted, before(from)) {
+                        diffs.push(PieceDiff::Promoted {
+                            at: to,
+                            piece_type: pawn.piece_type,
+                            side: pawn.side,
+                        });
+                    }
+
+                    diffs.push(PieceDiff::Moved { from: to, to: from });
+                }
+                PieceDiff::Removed { at } => {
+                    if let Some(piece) = before(at) {
+                        diffs.push(PieceDiff::Added {
+                            at,
+                            piece_type: piece.piece_type,
+                            side: piece.side,
+                        });
+                    }
+                }
+                PieceDiff::Promoted { at, .. } => {
+                    let moved = snapshot
+                        .diffs
+                        .iter()
+                        .any(|diff| matches!(diff, PieceDiff::Moved { from, .. } if *from == at));
+                    if let (false, Some(pawn)) = (moved, before(at)) {
+                        diffs.push(PieceDiff::Promoted {
+                            at,
+                            piece_type: pawn.piece_type,
+                            side: pawn.side,
+                        });
+                    }
+                }
+                PieceDiff::Added { .. } => (),
+            }
+        }
+
+        Some(diffs)
+    }
+
+    /// Plays the last undone change again
+    pub fn redo(&mut self) -> Option<Vec<PieceDiff>> {
+        let change = self.undone.pop()?;
+        Some(self.record_change(change))
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    fn move_piece(&mut self, start: BoardPos, end: BoardPos, diffs: &mut Vec<PieceDiff>) {
+        //* Important gets the piece and leaves a none value in its place
+        let mut piece = self.take(start).unwrap();
+
+        piece.move_piece(end, self.turn_num);
+        self.set_piece(Some(piece), end);
+        diffs.push(PieceDiff::Moved {
+            from: start,
+            to: end,
+        });
+    }
+
+    fn move_both_pieces(
+        &mut self,
+        start1: BoardPos,
+        start2: BoardPos,
+        end1: BoardPos,
+        end2: BoardPos,
+        diffs: &mut Vec<PieceDiff>,
+    ) {
+        let mut piece1 = self.take(start1).unwrap();
+        let mut piece2 = self.take(start2).unwrap();
+
+        piece1.move_piece(end1, self.turn_num);
+        piece2.move_piece(end2, self.turn_num);
+
+        self.set_piece(Some(piece1), end1);
+        self.set_piece(Some(piece2), end2);
+
+        // the second piece is moved out of the way first, a rook push ends
+        // on the square the pushed piece started on
+        diffs.push(PieceDiff::Moved {
+            from: start2,
+            to: end2,
+        });
+        diffs.push(PieceDiff::Moved {
+            from: start1,
+            to: end1,
+        });
+    }
+
+    fn promote_piece(
+        &mut self,
+        board_pos: BoardPos,
+        new_type: PieceType,
+        diffs: &mut Vec<PieceDiff>,
+    ) {
+        let piece = self.board[board_pos.x][board_pos.y].as_mut().unwrap();
+        piece.piece_type = new_type;
+
+        diffs.push(PieceDiff::Promoted {
+            at: board_pos,
+            piece_type: new_type,
+            side: piece.side,
+        });
+    }
+
+    pub fn get_piece(&self, board_pos: BoardPos) -> Option<Piece> {
+        self.board[board_pos.x][board_pos.y].clone()
+    }
+
+    fn set_piece(&mut self, piece: Option<Piece>, board_pos: BoardPos) {
+        self.board[board_pos.x][board_pos.y] = piece;
+    }
+
+    pub fn is_occupied(&self, board_pos: BoardPos) -> bool {
+        self.board[board_pos.x][board_pos.y].is_some()
+    }
+
+    pub fn is_occupied_and_friendly(&self, board_pos: BoardPos, other: Side) -> bool {
+        if let Some(piece) = self.get_piece(board_pos) {
+            if piece.side.is_friendly(&other) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn take(&mut self, board_pos: BoardPos) -> Option<Piece> {
+        self.board[board_pos.x][board_pos.y].take()
+    }
+}
+
+impl BoardPos {
+    pub fn new(x: usize, y: usize) -> Result<Self, String> {
+        if x >= BOARD_WIDTH || y >= BOARD_HEIGHT {
+            return Err(format!("BoardPos out of bounds: x: {}, y: {}", x, y));
+        }
+
+        Ok(Self { x, y })
+    }
+
+    /// Parses a square in algebraic notation like `e4`
+    pub fn from_algebraic(square: &str) -> Result<Self, String> {
+        let mut chars = square.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) => {
+                Self::new(file as usize - 'a' as usize, rank as usize - '1' as usize)
+            }
+            _ => Err(format!("Invalid square: {}", square)),
+        }
+    }
+
+    pub fn to_algebraic(self) -> String {
+        format!("{}{}", (b'a' + self.x as u8) as char, self.y + 1)
+    }
+}
+
+impl Side {
+    pub fn is_friendly(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    pub fn is_enemy(&self, other: &Self) -> bool {
+        !self.is_friendly(other)
+    }
+
+    pub fn opposite(&self) -> Side {
+        match self {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        }
+    }
+}
+
+impl BChange {
+    /// Every square the change can put a piece on or take one from
+    fn squares(&self) -> Vec<BoardPos> {
+        match *self {
+            BChange::Move { start, end } | BChange::Promotion { start, end, .. } => {
+                vec![start, end]
+            }
+            BChange::MoveDestroy { start, end, target } => vec![start, end, target],
+            BChange::BothMove {
+                start1,
+                start2,
+                end1,
+                end2,
+            }
+            | BChange::PushPremote {
+                start1,
+                start2,
+                end1,
+                end2,
+                ..
+            } => vec![start1, start2, end1, end2],
+        }
+    }
+
+    pub fn click_pos_to_activate_change(&self) -> BoardPos {
+        match self {
+            BChange::Move { end, .. } => *end,
+            BChange::MoveDestroy { end, .. } => *end,
+            BChange::BothMove { start2, .. } => *start2,
+            BChange::Promotion { end, .. } => *end,
+            BChange::PushPremote { start2, .. } => *start2,
+        }
+    }
+
+    pub fn is_capture(&self, position: &Position) -> bool {
+        match self {
+            BChange::MoveDestroy { .. } => true,
+            BChange::Promotion { end, .. } => position.is_occupied(*end),
+            _ => false,
+        }
+    }
+
+    pub fn moves_pawn(&self, position: &Position) -> bool {
+        let is_pawn = |pos: &BoardPos| match position.get_piece(*pos) {
+            Some(piece) => piece.piece_type == PieceType::Pawn,
+            None => false,
+        };
+
+        match self {
+            BChange::Move { start, .. } | BChange::MoveDestroy { start, .. } => is_pawn(start),
+            BChange::BothMove { start1, start2, .. } => is_pawn(start1) || is_pawn(start2),
+            BChange::Promotion { .. } | BChange::PushPremote { .. } => true,
+        }
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        matches!(
+            self,
+            BChange::Promotion { .. } | BChange::PushPremote { .. }
+        )
+    }
+
+    /// The square the promoted piece ends up on
+    pub fn promotion_square(&self) -> Option<BoardPos> {
+        match self {
+            BChange::Promotion { end, .. } => Some(*end),
+            BChange::PushPremote { end2, .. } => Some(*end2),
+            _ => None,
+        }
+    }
+
+    pub fn convert_to_promotion(&self, piece_type: PieceType) -> BChange {
+        match self {
+            BChange::Move { start, end } => BChange::Promotion {
+                start: *start,
+                end: *end,
+                piece_type,
+            },
+            BChange::MoveDestroy { start, end, .. } => BChange::Promotion {
+                start: *start,
+                end: *end,
+                piece_type,
+            },
+            BChange::BothMove {
+                start1,
+                end1,
+                start2,
+                end2,
+            }
+            | BChange::PushPremote {
+                start1,
+                end1,
+                start2,
+                end2,
+                ..
+            } => BChange::PushPremote {
+                start1: *start1,
+                end1: *end1,
+                start2: *start2,
+                end2: *end2,
+                piece_type,
+            },
+            BChange::Promotion { start, end, .. } => BChange::Promotion {
+                start: *start,
+                end: *end,
+                piece_type,
+            },
+        }
+    }
+}
+
+impl Piece {
+    pub fn move_piece(&mut self, target: BoardPos, turn_num: u32) {
+        self.distance_moved += {
+            let x = self.board_pos.x as isize - target.x as isize;
+            let y = self.board_pos.y as isize - target.y as isize;
+            (x.abs() + y.abs()) as u32
+        };
+
+        self.board_pos = target;
+        self.num_moves += 1;
+        self.turn_last_moved = turn_num;
+    }
+
+    pub fn turns_since_last_move(&self, turn_num: u32) -> u32 {
+        turn_num - self.turn_last_moved
+    }
+}
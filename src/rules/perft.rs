@@ -0,0 +1,38 @@
+use super::{BChange, Position};
+
+impl Position {
+    /// Counts the leaf nodes of the legal move tree `depth` half-moves deep
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.get_all_possible_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for change in moves {
+            self.make_change(change);
+            nodes += self.perft(depth - 1);
+            self.unmake_change();
+        }
+
+        nodes
+    }
+
+    /// The perft count below each legal move, handy for finding where two
+    /// move generators disagree
+    pub fn divide(&mut self, depth: u32) -> Vec<(BChange, u64)> {
+        let mut counts = Vec::new();
+
+        for change in self.get_all_possible_moves() {
+            self.make_change(change.clone());
+            counts.push((change, self.perft(depth.saturating_sub(1))));
+            self.unmake_change();
+        }
+
+        counts
+    }
+}
@@ -0,0 +1,354 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{BChange, GameStatus, Position, Side, START_FEN};
+
+const PGN_LINE_LENGTH: usize = 80;
+
+/// The tags written at the top of an exported game
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        Self {
+            event: "Casual game".to_owned(),
+            site: "?".to_owned(),
+            date: today(),
+            round: "-".to_owned(),
+            white: "?".to_owned(),
+            black: "?".to_owned(),
+        }
+    }
+}
+
+/// One numbered line of the move list, like `1. e4 e5`. The moves are indexes
+/// into the history, and either can be missing at the start or end of a game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MovePair {
+    pub number: u32,
+    pub white: Option<usize>,
+    pub black: Option<usize>,
+}
+
+/// The current date in the `YYYY.MM.DD` format PGN uses
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // converts days since the unix epoch to a civil date
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+impl Position {
+    /// The PGN result of the game, `*` while it is still going
+    pub fn result(&self) -> &'static str {
+        match self.game_status() {
+            GameStatus::Checkmate { winner }
+            | GameStatus::Resignation { winner }
+            | GameStatus::Timeout { winner } => match winner {
+                Side::White => "1-0",
+                Side::Black => "0-1",
+            },
+            GameStatus::Stalemate | GameStatus::Draw { .. } => "1/2-1/2",
+            GameStatus::Ongoing | GameStatus::Check => "*",
+        }
+    }
+
+    pub fn to_pgn(&self, tags: &PgnTags) -> String {
+        let result = self.result();
+
+        let mut pgn = String::new();
+        let mut add_tag = |name: &str, value: &str| {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('"', "\\\"")));
+        };
+
+        add_tag("Event", &tags.event);
+        add_tag("Site", &tags.site);
+        add_tag("Date", &tags.date);
+        add_tag("Round", &tags.round);
+        add_tag("White", &tags.white);
+        add_tag("Black", &tags.black);
+        add_tag("Result", result);
+
+        if self.start_fen != START_FEN {
+            add_tag("SetUp", "1");
+            add_tag("FEN", &self.start_fen);
+        }
+
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        for pair in self.move_pairs() {
+            match pair.white {
+                Some(i) => {
+                    tokens.push(format!("{}.", pair.number));
+                    tokens.push(self.history[i].san.clone());
+                }
+                None => tokens.push(format!("{}...", pair.number)),
+            }
+
+            if let Some(i) = pair.black {
+                tokens.push(self.history[i].san.clone());
+            }
+        }
+        tokens.push(result.to_owned());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() + 1 > PGN_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+
+    /// The recorded moves paired up by move number
+    pub fn move_pairs(&self) -> Vec<MovePair> {
+        // the turn number the recorded moves started from
+        let first_turn = self.turn_num - self.history.len() as u32;
+
+        let mut pairs: Vec<MovePair> = Vec::new();
+        for i in 0..self.history.len() {
+            let turn_num = first_turn + i as u32;
+
+            match pairs.last_mut() {
+                Some(pair) if !turn_num.is_multiple_of(2) => pair.black = Some(i),
+                _ => {
+                    let white = turn_num.is_multiple_of(2);
+                    pairs.push(MovePair {
+                        number: turn_num / 2 + 1,
+                        white: white.then_some(i),
+                        black: (!white).then_some(i),
+                    });
+                }
+            }
+        }
+
+        pairs
+    }
+}
+
+/// A game read from PGN, with the moves still in SAN
+#[derive(Default, Debug)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+}
+
+impl PgnGame {
+    /// Reads the first game in the PGN text
+    pub fn parse(pgn: &str) -> Result<Self, String> {
+        let mut game = PgnGame::default();
+        let mut movetext = String::new();
+
+        for line in pgn.lines() {
+            let line = line.trim();
+
+            if line.starts_with('[') {
+                // a tag after the moves belongs to the next game
+                if !game.moves.is_empty() || !movetext.trim().is_empty() {
+                    break;
+                }
+                game.tags.push(Self::parse_tag(line)?);
+            } else if !line.starts_with('%') {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+
+        game.moves = Self::parse_movetext(&movetext)?;
+        Ok(game)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn parse_tag(line: &str) -> Result<(String, String), String> {
+        let inner = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+            .ok_or(format!("Invalid tag: {}", line))?;
+
+        let (name, value) = inner
+            .split_once(' ')
+            .ok_or(format!("Invalid tag: {}", line))?;
+        let value = value
+            .trim()
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .ok_or(format!("Invalid tag value: {}", line))?;
+
+        Ok((name.to_owned(), value.replace("\\\"", "\"")))
+    }
+
+    /// Strips comments, variations, annotations, move numbers and the result
+    fn parse_movetext(movetext: &str) -> Result<Vec<String>, String> {
+        let mut moves = Vec::new();
+        let mut chars = movetext.chars().peekable();
+        let mut token = String::new();
+        let mut variation_depth = 0;
+
+        // moves inside variations are skipped
+        let finish_token = |token: &mut String, moves: &mut Vec<String>, depth: i32| {
+            // move numbers can be stuck to the move like `12.e4`
+            let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+            let is_result = matches!(token.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*");
+
+            if depth == 0 && !san.is_empty() && !is_result && !token.starts_with('$') {
+                moves.push(san.to_owned());
+            }
+            token.clear();
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    finish_token(&mut token, &mut moves, variation_depth);
+                    if !chars.by_ref().any(|c| c == '}') {
+                        return Err("Unclosed comment in PGN".to_owned());
+                    }
+                }
+                ';' => {
+                    finish_token(&mut token, &mut moves, variation_depth);
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '(' => {
+                    finish_token(&mut token, &mut moves, variation_depth);
+                    variation_depth += 1;
+                }
+                ')' => {
+                    finish_token(&mut token, &mut moves, variation_depth);
+                    variation_depth -= 1;
+                    if variation_depth < 0 {
+                        return Err("Unexpected ) in PGN".to_owned());
+                    }
+                }
+                c if c.is_whitespace() => finish_token(&mut token, &mut moves, variation_depth),
+                c => token.push(c),
+            }
+        }
+
+        if variation_depth > 0 {
+            return Err("Unclosed variation in PGN".to_owned());
+        }
+        finish_token(&mut token, &mut moves, variation_depth);
+
+        Ok(moves)
+    }
+}
+
+impl Position {
+    /// Finds the legal move that is written as the SAN token in the current position
+    pub fn resolve_san(&self, token: &str) -> Result<BChange, String> {
+        let normalize = |san: &str| -> String {
+            san.trim_end_matches(['+', '#', '!', '?'])
+                .replace('0', "O")
+                .replace('=', "")
+        };
+
+        let wanted = normalize(token);
+        let moves = self.get_all_possible_moves();
+        let mut matching: Vec<&BChange> = moves
+            .iter()
+            .filter(|change| normalize(&self.san(change)) == wanted)
+            .collect();
+
+        // a move that tells pieces apart when it did not need to, or leaves it out
+        // when it did, matches every piece on the file or rank written
+        if matching.is_empty() {
+            let (wanted, written) = split_disambiguation(&wanted);
+            matching = moves
+                .iter()
+                .filter(|change| {
+                    let start = change.mover_squares().0.to_algebraic();
+                    split_disambiguation(&normalize(&self.san(change))).0 == wanted
+                        && written.chars().all(|c| start.contains(c))
+                })
+                .collect();
+        }
+
+        let move_number = self.turn_num / 2 + 1;
+        let dots = match self.turn {
+            Side::White => ".",
+            Side::Black => "...",
+        };
+
+        match matching.len() {
+            1 => Ok(matching[0].clone()),
+            0 => Err(format!(
+                "Move {}{} {}: no legal move matches",
+                move_number, dots, token
+            )),
+            _ => Err(format!(
+                "Move {}{} {}: more than one legal move matches",
+                move_number, dots, token
+            )),
+        }
+    }
+}
+
+/// Splits a piece move into the move without the file or rank that tells it apart
+/// from another piece's and that file or rank, so `Nbd2` becomes `Nd2` and `b`.
+/// Pawn moves and castling are left alone
+fn split_disambiguation(san: &str) -> (String, String) {
+    let mut chars = san.chars();
+    let piece = match chars.next() {
+        Some(piece) if "KQRBN".contains(piece) => piece,
+        _ => return (san.to_owned(), String::new()),
+    };
+
+    // a rook push ends with the square the pushed piece lands on
+    let rest = chars.as_str();
+    let (main, push) = match rest.find('>') {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+    if main.len() < 2 {
+        return (san.to_owned(), String::new());
+    }
+
+    let (before, destination) = main.split_at(main.len() - 2);
+    let (written, capture) = match before.strip_suffix('x') {
+        Some(written) => (written, "x"),
+        None => (before, ""),
+    };
+    let san = format!("{}{}{}{}", piece, capture, destination, push);
+    (san, written.to_owned())
+}
@@ -0,0 +1,109 @@
+use super::{BChange, BoardPos, PieceType, Position};
+
+impl BChange {
+    /// The square the moving piece starts on and the square it ends up on
+    pub fn mover_squares(&self) -> (BoardPos, BoardPos) {
+        match self {
+            BChange::Move { start, end }
+            | BChange::MoveDestroy { start, end, .. }
+            | BChange::Promotion { start, end, .. } => (*start, *end),
+            BChange::BothMove { start1, end1, .. } | BChange::PushPremote { start1, end1, .. } => {
+                (*start1, *end1)
+            }
+        }
+    }
+
+    /// Where a piece pushed by a rook ends up
+    fn pushed_to(&self) -> Option<BoardPos> {
+        match self {
+            BChange::BothMove { end2, .. } | BChange::PushPremote { end2, .. } => Some(*end2),
+            _ => None,
+        }
+    }
+}
+
+impl Position {
+    /// Writes the change in standard algebraic notation without the check suffix.
+    /// Has to be called before the change is applied.
+    ///
+    /// Rook pushes have no standard notation, so they are written as the rook move
+    /// followed by `>` and the square the pushed piece lands on, e.g. `Rd3>d4`
+    pub fn san(&self, change: &BChange) -> String {
+        let (start, end) = change.mover_squares();
+        let piece = self.get_piece(start).unwrap();
+
+        if let BChange::BothMove { .. } = change {
+            if piece.piece_type == PieceType::King {
+                return match end.x > start.x {
+                    true => "O-O".to_owned(),
+                    false => "O-O-O".to_owned(),
+                };
+            }
+        }
+
+        let mut san = String::new();
+        let is_capture = change.is_capture(self);
+
+        if piece.piece_type == PieceType::Pawn {
+            if is_capture {
+                san.push(start.to_algebraic().remove(0));
+            }
+        } else {
+            san.push(piece.piece_type.letter());
+            san.push_str(&self.disambiguation(change));
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&end.to_algebraic());
+
+        if let Some(pushed_to) = change.pushed_to() {
+            san.push('>');
+            san.push_str(&pushed_to.to_algebraic());
+        }
+
+        if let BChange::Promotion { piece_type, .. } | BChange::PushPremote { piece_type, .. } =
+            change
+        {
+            san.push('=');
+            san.push(piece_type.letter());
+        }
+
+        san
+    }
+
+    /// The file, rank or both of the starting square when another piece of the
+    /// same type could make the same move
+    fn disambiguation(&self, change: &BChange) -> String {
+        let (start, end) = change.mover_squares();
+        let piece_type = self.get_piece(start).unwrap().piece_type;
+
+        let others: Vec<BoardPos> = self
+            .get_all_possible_moves()
+            .iter()
+            .filter(|other| {
+                let (other_start, other_end) = other.mover_squares();
+
+                other_start != start
+                    && other_end == end
+                    && other.pushed_to() == change.pushed_to()
+                    && self.get_piece(other_start).unwrap().piece_type == piece_type
+            })
+            .map(|other| other.mover_squares().0)
+            .collect();
+
+        if others.is_empty() {
+            return String::new();
+        }
+
+        let square = start.to_algebraic();
+        if others.iter().all(|other| other.x != start.x) {
+            square[..1].to_owned()
+        } else if others.iter().all(|other| other.y != start.y) {
+            square[1..].to_owned()
+        } else {
+            square
+        }
+    }
+}
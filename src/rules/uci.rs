@@ -0,0 +1,83 @@
+use super::{BChange, Position, Variant, START_FEN};
+
+impl BChange {
+    /// Writes the change in the long algebraic notation used by UCI, e.g. `e2e4` or `e7e8q`.
+    ///
+    /// Castling is written as the king move, and a rook push as the rook moving onto
+    /// the square of the piece it pushes
+    pub fn to_uci(&self) -> String {
+        let (start, end) = self.mover_squares();
+        let mut text = start.to_algebraic() + &end.to_algebraic();
+
+        match self {
+            BChange::Promotion { piece_type, .. } | BChange::PushPremote { piece_type, .. } => {
+                text.push(piece_type.letter().to_ascii_lowercase());
+            }
+            _ => (),
+        }
+
+        text
+    }
+}
+
+impl Position {
+    /// The UCI `position` command for this position, with the moves played since the start
+    /// so an engine can see repetitions
+    pub fn to_uci_position(&self) -> String {
+        // changes made with `make_change` are not in the history, so the moves are incomplete
+        if self.history.len() != self.previous.len() {
+            return format!("position fen {}", self.to_fen());
+        }
+
+        let mut command = format!("position fen {}", self.start_fen);
+        if !self.history.is_empty() {
+            command.push_str(" moves");
+            for record in self.history.iter() {
+                command.push(' ');
+                command.push_str(&record.change.to_uci());
+            }
+        }
+
+        command
+    }
+
+    /// Finds the legal change written as `text` in UCI notation
+    pub fn parse_uci_move(&self, text: &str) -> Result<BChange, String> {
+        let text = text.trim();
+
+        self.get_all_possible_moves()
+            .into_iter()
+            .find(|change| change.to_uci() == text)
+            .ok_or_else(|| format!("{} is not a legal move", text))
+    }
+
+    /// Reads a position written by `to_uci_position`, playing the moves so the history
+    /// and move list are complete
+    pub fn from_uci_position(command: &str, variant: Variant) -> Result<Self, String> {
+        let tokens: Vec<&str> = command.split_whitespace().collect();
+        let tokens = match tokens.as_slice() {
+            ["position", rest @ ..] => rest,
+            rest => rest,
+        };
+
+        let moves_at = tokens.iter().position(|&token| token == "moves");
+        let (setup, moves) = match moves_at {
+            Some(i) => (&tokens[..i], &tokens[i + 1..]),
+            None => (tokens, &[][..]),
+        };
+
+        let fen = match setup {
+            ["startpos"] => START_FEN.to_owned(),
+            ["fen", fields @ ..] => fields.join(" "),
+            _ => return Err(format!("Invalid position: {}", command)),
+        };
+
+        let mut position = Position::from_fen(&fen)?.with_variant(variant);
+        for text in moves {
+            let change = position.parse_uci_move(text)?;
+            position.apply_change(change);
+        }
+
+        Ok(position)
+    }
+}
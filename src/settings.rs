@@ -0,0 +1,327 @@
+use bevy::prelude::*;
+use chess::rules::{Clock, Side, TimeControl};
+
+use crate::{
+    board::Board,
+    components::{CoordinateLabel, SettingsButton, SettingsScreen},
+    constants::{
+        BUTTON_COLOR, BUTTON_HOVER_COLOR, CURRENT_MOVE_COLOR, HISTORY_PANEL_WIDTH, OVERLAY_COLOR,
+        SETTINGS_BUTTON_HEIGHT, TEXT_COLOR,
+    },
+    history::HistoryView,
+    network::Network,
+    resources::{Computer, DraggedPiece, NewGame, PromotionPicker},
+};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SettingsMenu::default())
+            .add_startup_system(init_settings)
+            .add_startup_system(spawn_settings_button)
+            .add_system(settings_buttons)
+            .add_system(show_settings_menu.after(settings_buttons))
+            .add_system(flip_board)
+            .add_system(orient_board.after(flip_board))
+            .add_system(show_coordinates.after(orient_board));
+    }
+}
+
+/// Whether the settings menu is covering the board
+#[derive(Default)]
+pub struct SettingsMenu {
+    pub open: bool,
+}
+
+/// Choices that last between games
+pub struct Settings {
+    pub orientation: Orientation,
+    /// Letters and numbers along the edges of the board
+    pub show_coordinates: bool,
+}
+
+/// Which side is drawn at the bottom of the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Orientation {
+    White,
+    Black,
+    /// Turns the board around after every move, for two players sharing the screen
+    SideToMove,
+}
+
+fn init_settings(mut commands: Commands, computer: Res<Computer>, network: Res<Network>) {
+    // play from the bottom of the board against the computer, or another player
+    // whose side is only known once the host says
+    let orientation = match computer.plays(Side::White) {
+        _ if network.is_online() => Orientation::SideToMove,
+        true => Orientation::Black,
+        false => Orientation::White,
+    };
+
+    commands.insert_resource(Settings {
+        orientation,
+        show_coordinates: true,
+    });
+}
+
+fn spawn_settings_button(mut commands: Commands, server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(
+                    Val::Px(HISTORY_PANEL_WIDTH),
+                    Val::Px(SETTINGS_BUTTON_HEIGHT),
+                ),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: BUTTON_COLOR.into(),
+            ..Default::default()
+        })
+        .insert(SettingsButton::Open)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font: server.load("fonts/DejaVuSans.ttf"),
+                    font_size: 28.0,
+                    color: TEXT_COLOR,
+                },
+            ));
+        });
+}
+
+fn show_settings_menu(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    menu: Res<SettingsMenu>,
+    settings: Res<Settings>,
+    clock: Res<Clock>,
+    q_screen: Query<Entity, With<SettingsScreen>>,
+) {
+    if !menu.is_changed() {
+        return;
+    }
+
+    for entity in q_screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !menu.open {
+        return;
+    }
+
+    let font = server.load("fonts/DejaVuSans.ttf");
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            margin: UiRect::all(Val::Px(8.0)),
+            ..Default::default()
+        })
+    };
+
+    let mut time_controls = vec![(SettingsButton::TimeControl(None), "No clock".to_owned())];
+    for control in TimeControl::PRESETS {
+        time_controls.push((
+            SettingsButton::TimeControl(Some(control)),
+            control.to_string(),
+        ));
+    }
+
+    let orientations = [
+        (Orientation::White, "White at the bottom"),
+        (Orientation::Black, "Black at the bottom"),
+        (Orientation::SideToMove, "Side to move at the bottom"),
+    ]
+    .map(|(orientation, label)| (SettingsButton::Orientation(orientation), label.to_owned()));
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: OVERLAY_COLOR.into(),
+            ..Default::default()
+        })
+        .insert(SettingsScreen)
+        .with_children(|parent| {
+            let spawn_button = |parent: &mut ChildBuilder, button: SettingsButton, label: &str| {
+                let color = match is_chosen(button, &clock, &settings) {
+                    true => CURRENT_MOVE_COLOR,
+                    false => BUTTON_COLOR,
+                };
+
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(360.0), Val::Px(40.0)),
+                            margin: UiRect::all(Val::Px(3.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: color.into(),
+                        ..Default::default()
+                    })
+                    .insert(button)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(text(label, 22.0));
+                    });
+            };
+
+            parent.spawn_bundle(text("Time control", 36.0));
+            parent.spawn_bundle(text("Picking one starts a new game", 18.0));
+            for (button, label) in &time_controls {
+                spawn_button(parent, *button, label);
+            }
+
+            parent.spawn_bundle(text("Board", 36.0));
+            for (button, label) in &orientations {
+                spawn_button(parent, *button, label);
+            }
+            spawn_button(parent, SettingsButton::Coordinates, "Coordinates");
+
+            spawn_button(parent, SettingsButton::Close, "Close");
+        });
+}
+
+/// Whether the button is the setting being used at the moment
+fn is_chosen(button: SettingsButton, clock: &Clock, settings: &Settings) -> bool {
+    match button {
+        SettingsButton::TimeControl(control) => control == clock.control(),
+        SettingsButton::Orientation(orientation) => orientation == settings.orientation,
+        SettingsButton::Coordinates => settings.show_coordinates,
+        SettingsButton::Open | SettingsButton::Close => false,
+    }
+}
+
+fn settings_buttons(
+    mut menu: ResMut<SettingsMenu>,
+    mut settings: ResMut<Settings>,
+    mut clock: ResMut<Clock>,
+    mut new_game: EventWriter<NewGame>,
+    network: Res<Network>,
+    mut q_button: Query<(&Interaction, &SettingsButton, &mut UiColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in q_button.iter_mut() {
+        match interaction {
+            Interaction::Clicked => match *button {
+                SettingsButton::Open => menu.open = !menu.open,
+                SettingsButton::TimeControl(_) if network.is_guest() => {
+                    println!("Only the host can change the time control");
+                }
+                SettingsButton::TimeControl(control) => {
+                    *clock = Clock::new(control);
+                    new_game.send(NewGame);
+                    menu.open = false;
+                }
+                SettingsButton::Orientation(orientation) => {
+                    settings.orientation = orientation;
+                    // show which one is picked now
+                    menu.set_changed();
+                }
+                SettingsButton::Coordinates => {
+                    settings.show_coordinates = !settings.show_coordinates;
+                    menu.set_changed();
+                }
+                SettingsButton::Close => menu.open = false,
+            },
+            Interaction::Hovered => *color = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => {
+                *color = match is_chosen(*button, &clock, &settings) {
+                    true => CURRENT_MOVE_COLOR.into(),
+                    false => BUTTON_COLOR.into(),
+                }
+            }
+        }
+    }
+}
+
+/// Ctrl+F turns the board around
+fn flip_board(board: Res<Board>, mut settings: ResMut<Settings>, input: Res<Input<KeyCode>>) {
+    if input.just_pressed(KeyCode::F) && input.pressed(KeyCode::LControl) {
+        settings.orientation = match board.is_flipped() {
+            true => Orientation::White,
+            false => Orientation::Black,
+        };
+    }
+}
+
+/// Keeps the side the settings ask for at the bottom of the board
+fn orient_board(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut picker: ResMut<PromotionPicker>,
+    mut dragged: ResMut<DraggedPiece>,
+    settings: Res<Settings>,
+    computer: Res<Computer>,
+    network: Res<Network>,
+    view: Res<HistoryView>,
+) {
+    let bottom = match settings.orientation {
+        Orientation::White => Side::White,
+        Orientation::Black => Side::Black,
+        // against the computer or over the network only the player here needs to see
+        // the board their way, and looking back through the game should not spin the
+        // board around
+        Orientation::SideToMove => match (network.side(), computer.side) {
+            (Some(side), _) => side,
+            (None, Some(side)) => side.opposite(),
+            // spectators have no side of their own
+            (None, None) if network.is_online() => Side::White,
+            (None, None) => view.live_position(&board).turn(),
+        },
+    };
+
+    let flipped = bottom == Side::Black;
+    if board.is_flipped() != flipped {
+        // the picker and a dragged piece would be left on the old squares
+        picker.close(&mut commands);
+        dragged.piece = None;
+        board.set_flipped(&mut commands, flipped);
+    }
+}
+
+/// Draws the coordinates again when they are turned on or off, or the board flips
+fn show_coordinates(
+    mut commands: Commands,
+    server: Res<AssetServer>,
+    board: Res<Board>,
+    settings: Res<Settings>,
+    mut drawn: Local<Option<(bool, bool)>>,
+    q_labels: Query<Entity, With<CoordinateLabel>>,
+) {
+    let wanted = (settings.show_coordinates, board.is_flipped());
+    if *drawn == Some(wanted) {
+        return;
+    }
+    *drawn = Some(wanted);
+
+    for entity in q_labels.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if settings.show_coordinates {
+        board.spawn_coordinates(&mut commands, server.load("fonts/DejaVuSans.ttf"));
+    }
+}
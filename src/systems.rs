@@ -1,21 +1,44 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use crate::{
     board::Board,
     components::{Moveable, Tile},
-    constants::{PIECE_Z_LAYER, SELECTED_COLOR},
+    constants::{CHECK_COLOR, DRAG_Z_LAYER, LAST_MOVE_COLOR, PIECE_Z_LAYER, SELECTED_COLOR},
+    game_over::GameOver,
+    history::HistoryView,
+    network::Network,
     resources::MouseInfo,
-    resources::{HiglightedSquares, SelectedSquare},
+    resources::{
+        Computer, DraggedPiece, HiglightedSquares, NewGame, PgnReplay, PromotionPicker,
+        SelectedSquare,
+    },
+    settings::SettingsMenu,
 };
 use bevy::prelude::*;
+use chess::{
+    net::Message,
+    rules::{pgn::PgnTags, BChange, Clock, GameStatus},
+};
+use futures_lite::future;
 
 pub struct SystemsPlugin;
 
 impl Plugin for SystemsPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(on_click)
+            .add_system(drag_piece.after(on_click))
+            .add_system(drop_piece.after(drag_piece))
             .add_system(highlight_squares)
             .add_system(move_pieces)
+            .add_system(replay_pgn)
+            .add_system(computer_move)
             .add_system(undo)
-            .add_system(restart);
+            .add_system(redo)
+            .add_system(restart)
+            .add_system(resign)
+            .add_system(claim_draw)
+            .add_system(copy_fen)
+            .add_system(save_pgn);
     }
 }
 
@@ -24,16 +47,55 @@ pub fn on_click(
     mut board: ResMut<Board>,
     mut selected: ResMut<SelectedSquare>,
     mut highlighted: ResMut<HiglightedSquares>,
+    mut picker: ResMut<PromotionPicker>,
+    mut clock: ResMut<Clock>,
+    mut dragged: ResMut<DraggedPiece>,
+    mut network: ResMut<Network>,
+    replay: Res<PgnReplay>,
+    computer: Res<Computer>,
+    game_over: Res<GameOver>,
+    view: Res<HistoryView>,
+    menu: Res<SettingsMenu>,
     mouse: Res<MouseInfo>,
 ) {
-    // if there was no click, don't do anything
-    if !mouse.just_clicked {
+    // if there was no click, a game is being replayed, it is the computer's or the
+    // other player's turn, an earlier position is shown, a menu is open or the game
+    // is over, don't do anything
+    if !mouse.just_clicked
+        || !replay.moves.is_empty()
+        || computer.plays(board.position().turn())
+        || !network.can_move(board.position().turn())
+        || view.is_viewing()
+        || menu.open
+        || game_over.status.is_some()
+    {
         return;
     }
 
     selected.changed = true;
+    highlighted.squares.clear();
     let target_square = mouse.board_pos;
 
+    // a click while choosing a promotion either picks a piece or cancels the move
+    if let Some(change) = picker.change.clone() {
+        let choice = target_square.and_then(|square| picker.choice_at(square));
+        picker.close(&mut commands);
+
+        if let Some(piece_type) = choice {
+            play_move(
+                &mut commands,
+                &mut board,
+                &mut clock,
+                &mut network,
+                change.convert_to_promotion(piece_type),
+            );
+        }
+
+        selected.piece = None;
+        selected.tile = None;
+        return;
+    }
+
     // if there is no square under the mouse, deselect and exit
     if target_square.is_none() {
         selected.piece = None;
@@ -45,33 +107,196 @@ pub fn on_click(
     // dbg!("Clicked on board at: {}, {}", target_square.x, target_square.y);
 
     if let Some(piece) = &selected.piece {
-        if let Some(change) = board.check_valid_change(piece.board_pos, target_square) {
-            println!("Valid move");
-            board.apply_board_change(&mut commands, change);
+        if let Some(change) = board
+            .position()
+            .check_valid_change(piece.board_pos, target_square)
+        {
+            if change.is_promotion() {
+                picker.open(&mut commands, &board, change);
+            } else {
+                play_move(&mut commands, &mut board, &mut clock, &mut network, change);
+            }
+
             selected.piece = None;
             selected.tile = None;
             return;
         }
     }
 
-    selected.piece = board.get_piece(target_square);
+    selected.piece = board.position().get_piece(target_square);
     selected.tile = Some(board.get_tile_entity(target_square));
 
     if let Some(piece) = &selected.piece {
-        let moves = board.get_possible_moves(piece.board_pos);
+        let moves = board.position().get_possible_moves(piece.board_pos);
         *highlighted = HiglightedSquares::from_board_changes(&board, moves);
+
+        // the piece to move can also be dragged to its square
+        if board.position().is_turn(piece.side) {
+            if let Some(entity) = board.get_piece_entity(target_square) {
+                commands.entity(entity).remove::<Moveable>();
+                dragged.piece = Some((entity, target_square));
+            }
+        }
     }
 }
 
-pub fn highlight_squares(
+/// Keeps the dragged piece under the mouse
+fn drag_piece(
+    dragged: Res<DraggedPiece>,
+    mouse: Res<MouseInfo>,
+    mut q_transform: Query<&mut Transform>,
+) {
+    let (Some((entity, _)), Some(cords)) = (dragged.piece, mouse.world_cords) else {
+        return;
+    };
+
+    if let Ok(mut transform) = q_transform.get_mut(entity) {
+        transform.translation = cords.truncate().extend(DRAG_Z_LAYER);
+    }
+}
+
+/// Plays the move the dragged piece was dropped on, or sends it back to its square
+fn drop_piece(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
     mut selected: ResMut<SelectedSquare>,
     mut highlighted: ResMut<HiglightedSquares>,
+    mut picker: ResMut<PromotionPicker>,
+    mut clock: ResMut<Clock>,
+    mut dragged: ResMut<DraggedPiece>,
+    mut network: ResMut<Network>,
+    game_over: Res<GameOver>,
+    mouse: Res<MouseInfo>,
+    mut q_transform: Query<&mut Transform>,
+) {
+    if !mouse.just_released {
+        return;
+    }
+    let Some((entity, from)) = dragged.piece.take() else {
+        return;
+    };
+    let Ok(mut transform) = q_transform.get_mut(entity) else {
+        return;
+    };
+    let dropped_at = transform.translation.truncate();
+
+    // letting go on the same square is a click, the piece stays selected
+    if mouse.board_pos == Some(from) {
+        transform.translation = board.to_world_pos(from).extend(PIECE_Z_LAYER);
+        return;
+    }
+
+    let change = mouse
+        .board_pos
+        .filter(|_| game_over.status.is_none())
+        .and_then(|to| board.position().check_valid_change(from, to));
+
+    match change {
+        Some(change) if change.is_promotion() => {
+            commands
+                .entity(entity)
+                .insert(Moveable::new(dropped_at, board.to_world_pos(from)));
+            picker.open(&mut commands, &board, change);
+        }
+        Some(change) => {
+            play_move(&mut commands, &mut board, &mut clock, &mut network, change);
+
+            // carry on from where the piece was dropped rather than its old square
+            if let Some(square) = board.find_piece_entity(entity) {
+                commands
+                    .entity(entity)
+                    .insert(Moveable::new(dropped_at, board.to_world_pos(square)));
+            }
+        }
+        None => {
+            commands
+                .entity(entity)
+                .insert(Moveable::new(dropped_at, board.to_world_pos(from)));
+        }
+    }
+
+    selected.piece = None;
+    selected.tile = None;
+    selected.changed = true;
+    highlighted.squares.clear();
+}
+
+/// Plays a move made on this machine. A guest in a network game waits for the
+/// host to play it
+fn play_move(
+    commands: &mut Commands,
+    board: &mut Board,
+    clock: &mut Clock,
+    network: &mut Network,
+    change: BChange,
+) {
+    match network.is_guest() {
+        true => network.request_move(&change),
+        false => apply_change(commands, board, clock, change),
+    }
+}
+
+pub fn apply_change(
+    commands: &mut Commands,
+    board: &mut Board,
+    clock: &mut Clock,
+    change: BChange,
+) {
+    clock.press(board.position().turn());
+    play_change(commands, board, change);
+}
+
+/// Plays a change without touching the clock and says how the game stands after it
+fn play_change(commands: &mut Commands, board: &mut Board, change: BChange) {
+    board.apply_board_change(commands, change);
+
+    if let Some(record) = board.position().history().last() {
+        println!("Played {}", record.san);
+    }
+
+    let status = board.position().game_status();
+    match status {
+        GameStatus::Ongoing => (),
+        GameStatus::Check => println!("Check"),
+        GameStatus::Checkmate { winner } => println!("Checkmate, {:?} wins", winner),
+        GameStatus::Stalemate => println!("Stalemate"),
+        GameStatus::Draw { reason } => println!("Draw by {}", reason),
+        GameStatus::Resignation { winner } => println!("{:?} wins by resignation", winner),
+        GameStatus::Timeout { winner } => println!("{:?} wins on time", winner),
+    }
+
+    if let Some(reason) = board.position().claimable_draw() {
+        if !status.is_over() {
+            println!(
+                "A draw can be claimed by {}, press Ctrl+D to claim it",
+                reason
+            );
+        }
+    }
+}
+
+pub fn highlight_squares(
+    board: Res<Board>,
+    mut selected: ResMut<SelectedSquare>,
+    highlighted: Res<HiglightedSquares>,
     mut q_tile: Query<(Entity, &mut Sprite, &Tile)>,
 ) {
-    if !selected.changed {
+    if !selected.changed && !board.is_changed() {
         return;
     }
 
+    let position = board.position();
+    let last_move: Vec<Entity> = match position.history().last() {
+        Some(record) => {
+            let (from, to) = record.change.mover_squares();
+            vec![board.get_tile_entity(from), board.get_tile_entity(to)]
+        }
+        None => Vec::new(),
+    };
+    let checked_king = position
+        .checked_king()
+        .map(|square| board.get_tile_entity(square));
+
     let entity_in_highlighted = |entity: Entity| -> Option<Color> {
         for (e, h) in highlighted.squares.iter() {
             if *e == entity {
@@ -82,31 +307,121 @@ pub fn highlight_squares(
         None
     };
 
+    // the selection goes over the moves it has, which go over check and the last move
     for (entity, mut sprite, tile) in q_tile.iter_mut() {
         if Some(entity) == selected.tile {
             sprite.color = SELECTED_COLOR;
         } else if let Some(color) = entity_in_highlighted(entity) {
             sprite.color = color;
+        } else if Some(entity) == checked_king {
+            sprite.color = CHECK_COLOR;
+        } else if last_move.contains(&entity) {
+            sprite.color = mix(tile.normal_color, LAST_MOVE_COLOR);
         } else {
             sprite.color = tile.normal_color;
         }
     }
 
-    // clear the highlighted squares
-    highlighted.squares.clear();
     selected.changed = false;
 }
 
+/// Halfway between two colours, so light and dark squares still look different
+fn mix(a: Color, b: Color) -> Color {
+    let [r1, g1, b1, _] = a.as_rgba_f32();
+    let [r2, g2, b2, _] = b.as_rgba_f32();
+
+    Color::rgb((r1 + r2) / 2.0, (g1 + g2) / 2.0, (b1 + b2) / 2.0)
+}
+
 fn undo(
     mut commands: Commands,
     mut board: ResMut<Board>,
     mut selected: ResMut<SelectedSquare>,
     mut highlighted: ResMut<HiglightedSquares>,
+    mut picker: ResMut<PromotionPicker>,
+    mut replay: ResMut<PgnReplay>,
+    mut computer: ResMut<Computer>,
+    network: Res<Network>,
+    clock: Res<Clock>,
+    view: Res<HistoryView>,
     input: Res<Input<KeyCode>>,
 ) {
-    if input.just_pressed(KeyCode::Z) && input.pressed(KeyCode::LControl) {
+    if view.is_viewing() {
+        return;
+    }
+
+    // Ctrl+Shift+Z is redo
+    if input.just_pressed(KeyCode::Z)
+        && input.pressed(KeyCode::LControl)
+        && !input.pressed(KeyCode::LShift)
+    {
+        // the other side would never hear about it
+        if network.is_online() {
+            println!("Moves can not be taken back in network games");
+            return;
+        }
+        // the time spent on the move and the increment it earned would stay
+        if clock.control().is_some() {
+            println!("Moves can not be taken back in timed games");
+            return;
+        }
+
+        computer.stop_thinking();
         board.undo_last_change(&mut commands);
 
+        // take back the computer's reply as well, or it would just play it again
+        if computer.plays(board.position().turn()) {
+            board.undo_last_change(&mut commands);
+        }
+
+        picker.close(&mut commands);
+        replay.moves.clear();
+        selected.piece = None;
+        selected.tile = None;
+        selected.changed = true;
+        highlighted.squares.clear();
+    }
+}
+
+fn redo(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut selected: ResMut<SelectedSquare>,
+    mut highlighted: ResMut<HiglightedSquares>,
+    mut picker: ResMut<PromotionPicker>,
+    mut replay: ResMut<PgnReplay>,
+    mut computer: ResMut<Computer>,
+    network: Res<Network>,
+    clock: Res<Clock>,
+    view: Res<HistoryView>,
+    input: Res<Input<KeyCode>>,
+) {
+    if view.is_viewing() || !input.pressed(KeyCode::LControl) {
+        return;
+    }
+
+    let shift = input.pressed(KeyCode::LShift);
+    if input.just_pressed(KeyCode::Y) || shift && input.just_pressed(KeyCode::Z) {
+        if network.is_online() {
+            println!("Moves can not be taken back in network games");
+            return;
+        }
+        // the time spent on the move and the increment it earned would stay
+        if clock.control().is_some() {
+            println!("Moves can not be taken back in timed games");
+            return;
+        }
+
+        computer.stop_thinking();
+        board.redo_change(&mut commands);
+
+        // redo the computer's reply as well, the same way undo took it back
+        if computer.plays(board.position().turn()) {
+            board.redo_change(&mut commands);
+        }
+
+        picker.close(&mut commands);
+        replay.moves.clear();
         selected.piece = None;
         selected.tile = None;
         selected.changed = true;
@@ -119,11 +434,32 @@ fn restart(
     mut board: ResMut<Board>,
     mut selected: ResMut<SelectedSquare>,
     mut highlighted: ResMut<HiglightedSquares>,
+    mut picker: ResMut<PromotionPicker>,
+    mut replay: ResMut<PgnReplay>,
+    mut computer: ResMut<Computer>,
+    mut game_over: ResMut<GameOver>,
+    mut view: ResMut<HistoryView>,
+    mut clock: ResMut<Clock>,
+    mut new_game: EventReader<NewGame>,
+    network: Res<Network>,
     input: Res<Input<KeyCode>>,
 ) {
-    if input.just_pressed(KeyCode::R) && input.pressed(KeyCode::LControl) {
+    let clicked_new_game = new_game.iter().count() > 0;
+
+    if clicked_new_game || input.just_pressed(KeyCode::R) && input.pressed(KeyCode::LControl) {
+        if network.is_guest() {
+            println!("Only the host can start a new game");
+            return;
+        }
+
+        computer.stop_thinking();
+        view.clear();
+        *clock = Clock::new(clock.control());
         board.restart_game(&mut commands);
+        *game_over = GameOver::default();
 
+        picker.close(&mut commands);
+        replay.moves.clear();
         selected.piece = None;
         selected.tile = None;
         selected.changed = true;
@@ -131,6 +467,172 @@ fn restart(
     }
 }
 
+fn resign(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut computer: ResMut<Computer>,
+    mut picker: ResMut<PromotionPicker>,
+    mut network: ResMut<Network>,
+    game_over: Res<GameOver>,
+    view: Res<HistoryView>,
+    input: Res<Input<KeyCode>>,
+) {
+    if game_over.status.is_some() || view.is_viewing() {
+        return;
+    }
+
+    if input.just_pressed(KeyCode::G) && input.pressed(KeyCode::LControl) {
+        // against the computer or over the network it is always the player here
+        // who gives up
+        let side = match (network.side(), computer.side) {
+            (Some(side), _) => side,
+            (None, Some(side)) => side.opposite(),
+            (None, None) if network.is_online() => return,
+            (None, None) => board.position().turn(),
+        };
+
+        computer.stop_thinking();
+        picker.close(&mut commands);
+        board.resign(side);
+        network.send(&Message::Resign(side));
+        println!("{:?} resigned", side);
+    }
+}
+
+fn claim_draw(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut computer: ResMut<Computer>,
+    mut picker: ResMut<PromotionPicker>,
+    network: Res<Network>,
+    game_over: Res<GameOver>,
+    view: Res<HistoryView>,
+    input: Res<Input<KeyCode>>,
+) {
+    if game_over.status.is_some() || view.is_viewing() {
+        return;
+    }
+
+    if input.just_pressed(KeyCode::D) && input.pressed(KeyCode::LControl) {
+        if network.is_online() {
+            println!("Draws can not be claimed in network games");
+            return;
+        }
+
+        // the computer could be thinking about its move in the drawn position
+        computer.stop_thinking();
+        picker.close(&mut commands);
+
+        match board.claim_draw() {
+            Some(reason) => println!("Draw by {}", reason),
+            None => println!("There is no draw to claim"),
+        }
+    }
+}
+
+fn replay_pgn(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut replay: ResMut<PgnReplay>,
+    view: Res<HistoryView>,
+    q_moveable: Query<&Moveable>,
+) {
+    // wait for the previous move to finish animating
+    if replay.moves.is_empty() || !q_moveable.is_empty() || view.is_viewing() {
+        return;
+    }
+
+    let token = replay.moves.pop_front().unwrap();
+    match board.position().resolve_san(&token) {
+        // a saved game has no times to add increments to
+        Ok(change) => play_change(&mut commands, &mut board, change),
+        Err(err) => {
+            println!("Could not replay the PGN: {}", err);
+            replay.moves.clear();
+        }
+    }
+}
+
+fn computer_move(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut computer: ResMut<Computer>,
+    mut clock: ResMut<Clock>,
+    replay: Res<PgnReplay>,
+    game_over: Res<GameOver>,
+    mut view: ResMut<HistoryView>,
+    q_moveable: Query<&Moveable>,
+) {
+    // check if the search running in the background has finished
+    if let Some(task) = &mut computer.task {
+        if let Some(result) = future::block_on(future::poll_once(task)) {
+            computer.task = None;
+
+            match result {
+                Ok(Some(change)) => {
+                    // the computer's move brings the game back on the board, its clock
+                    // was running while the player looked back
+                    view.back_to_game(&mut commands, &mut board);
+                    apply_change(&mut commands, &mut board, &mut clock, change);
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    println!("{}, you play both sides now", err);
+                    computer.side = None;
+                }
+            }
+        }
+
+        return;
+    }
+
+    // wait for the last move to finish animating before thinking about the next one
+    let live = view.live_position(&board);
+    if !computer.plays(live.turn())
+        || !replay.moves.is_empty()
+        || !q_moveable.is_empty()
+        || game_over.status.is_some()
+    {
+        return;
+    }
+
+    // the result screen may not be up yet in the frame the game ends
+    if !live.game_status().is_over() {
+        let side = live.turn();
+        let think_time = clock.think_time(side, computer.think_time);
+        computer.start_thinking(live, think_time);
+    }
+}
+
+fn copy_fen(board: Res<Board>, input: Res<Input<KeyCode>>) {
+    if input.just_pressed(KeyCode::C) && input.pressed(KeyCode::LControl) {
+        let fen = board.position().to_fen();
+        println!("FEN: {}", fen);
+
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(fen)) {
+            Ok(()) => println!("Copied FEN to the clipboard"),
+            Err(err) => println!("Could not copy FEN to the clipboard: {}", err),
+        }
+    }
+}
+
+fn save_pgn(board: Res<Board>, view: Res<HistoryView>, input: Res<Input<KeyCode>>) {
+    if input.just_pressed(KeyCode::S) && input.pressed(KeyCode::LControl) {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("game_{}.pgn", secs);
+
+        // the whole game is saved, even while an earlier position is shown
+        let pgn = view.live_position(&board).to_pgn(&PgnTags::default());
+        match std::fs::write(&path, pgn) {
+            Ok(()) => println!("Saved the game to {}", path),
+            Err(err) => println!("Could not save the game: {}", err),
+        }
+    }
+}
+
 fn move_pieces(
     mut commands: Commands,
     mut q_moveable: Query<(Entity, &mut Moveable, &mut Transform)>,
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use chess::ai::{search, SearchLimits, MATE_SCORE};
+use chess::rules::Position;
+
+mod common;
+
+use common::square;
+
+fn limits(max_depth: u32) -> SearchLimits {
+    SearchLimits {
+        max_depth,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    let result = search(&position, &limits(3)).unwrap();
+
+    assert_eq!(position.san(&result.best_move), "Ra8");
+    assert_eq!(result.score, MATE_SCORE - 1);
+}
+
+#[test]
+fn takes_a_free_queen() {
+    let position = Position::from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+    let result = search(&position, &limits(3)).unwrap();
+
+    assert_eq!(
+        result.best_move.click_pos_to_activate_change(),
+        square("d5")
+    );
+}
+
+#[test]
+fn stops_when_out_of_time() {
+    let limits = SearchLimits {
+        time: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+
+    let result = search(&Position::new(), &limits).unwrap();
+    assert!(result.depth < limits.max_depth);
+}
+
+#[test]
+fn no_move_when_checkmated() {
+    let position = Position::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+    assert!(search(&position, &limits(3)).is_none());
+}
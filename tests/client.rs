@@ -0,0 +1,178 @@
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{
+    net::{Client, Connection, Message},
+    rules::{Position, Side, Variant},
+};
+
+mod common;
+
+use common::TIMEOUT;
+
+const RETRY: Duration = Duration::from_millis(20);
+
+/// Stands in for a host, so the client can be told anything
+fn listen() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    (listener, address)
+}
+
+/// Waits for the client to connect, polling it meanwhile so it can try
+fn accept(listener: &TcpListener, client: &mut Client) -> Connection {
+    let started = Instant::now();
+    loop {
+        if let Ok((stream, _)) = listener.accept() {
+            stream.set_nonblocking(false).unwrap();
+            return Connection::new(stream).unwrap();
+        }
+        assert!(started.elapsed() < TIMEOUT, "The client did not connect");
+        client.poll(false);
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Polls the client until `done` says so
+fn poll_until(client: &mut Client, done: impl Fn(&Client) -> bool) -> Vec<Message> {
+    let started = Instant::now();
+    let mut messages = Vec::new();
+    while !done(client) {
+        assert!(started.elapsed() < TIMEOUT, "The client got stuck");
+        while let Some(message) = client.poll(false) {
+            messages.push(message.unwrap());
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    messages
+}
+
+fn welcome(side: Option<Side>) -> Message {
+    Message::Welcome {
+        side,
+        variant: Variant::Standard,
+        game: Position::new().to_uci_position(),
+    }
+}
+
+/// A client that has been given black's seat in game 3
+fn seated_client(listener: &TcpListener, address: &str) -> (Client, Connection) {
+    let mut client = Client::connect(address, &Message::Play)
+        .unwrap()
+        .with_retry_interval(RETRY);
+    let mut host = accept(listener, &mut client);
+    assert_eq!(host.recv(TIMEOUT), Ok(Message::Play));
+
+    let seat = Message::Seat {
+        game: 3,
+        token: "0123abcd".to_owned(),
+        grace: Duration::from_millis(200),
+    };
+    host.send(&seat).unwrap();
+    host.send(&welcome(Some(Side::Black))).unwrap();
+    let messages = poll_until(&mut client, |client| client.side().is_some());
+    assert_eq!(messages, [seat, welcome(Some(Side::Black))]);
+    assert_eq!(client.seat(), Some(&(3, "0123abcd".to_owned())));
+
+    (client, host)
+}
+
+#[test]
+fn a_lost_connection_is_taken_back_with_the_seat() {
+    let (listener, address) = listen();
+    let (mut client, host) = seated_client(&listener, &address);
+
+    drop(host);
+    poll_until(&mut client, |client| !client.is_connected());
+    assert!(client.is_reconnecting());
+    assert!(client.send(&Message::Play).is_err());
+
+    let mut host = accept(&listener, &mut client);
+    assert_eq!(
+        host.recv(TIMEOUT),
+        Ok(Message::Resume {
+            game: 3,
+            token: "0123abcd".to_owned()
+        })
+    );
+    // the connection made in the background is picked up by the next poll
+    poll_until(&mut client, |client| client.is_connected());
+    assert!(client.is_reconnecting());
+
+    host.send(&welcome(Some(Side::Black))).unwrap();
+    poll_until(&mut client, |client| !client.is_reconnecting());
+    assert_eq!(client.side(), Some(Side::Black));
+}
+
+#[test]
+fn a_refused_seat_is_not_asked_for_again() {
+    let (listener, address) = listen();
+    let (mut client, host) = seated_client(&listener, &address);
+
+    drop(host);
+    let mut host = accept(&listener, &mut client);
+    assert!(matches!(host.recv(TIMEOUT), Ok(Message::Resume { .. })));
+
+    // the refusal is handled by the client, not passed on
+    host.send(&Message::Reject("That is not a seat".to_owned()))
+        .unwrap();
+    let messages = poll_until(&mut client, |client| client.seat().is_none());
+    assert!(messages.is_empty());
+
+    drop(host);
+    poll_until(&mut client, |client| !client.is_connected());
+    assert!(!client.is_reconnecting());
+}
+
+#[test]
+fn the_client_gives_up_when_the_host_would_have() {
+    let (listener, address) = listen();
+    // the host said it waits 200ms with the seat
+    let (mut client, host) = seated_client(&listener, &address);
+
+    // nobody is listening any more
+    drop(listener);
+    drop(host);
+    poll_until(&mut client, |client| !client.is_connected());
+    assert!(client.is_reconnecting());
+    poll_until(&mut client, |client| !client.is_reconnecting());
+    assert!(!client.is_connected());
+}
+
+#[test]
+fn there_is_nothing_to_come_back_to_after_the_game() {
+    let (listener, address) = listen();
+    let (mut client, host) = seated_client(&listener, &address);
+
+    drop(host);
+    let started = Instant::now();
+    while client.is_connected() {
+        assert!(started.elapsed() < TIMEOUT, "The client did not notice");
+        client.poll(true);
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(!client.is_reconnecting());
+
+    thread::sleep(RETRY * 3);
+    client.poll(true);
+    assert!(listener.accept().is_err());
+}
+
+#[test]
+fn spectators_have_no_seat_to_come_back_to() {
+    let (listener, address) = listen();
+    let mut client = Client::connect(&address, &Message::Watch(None))
+        .unwrap()
+        .with_retry_interval(RETRY);
+    let mut host = accept(&listener, &mut client);
+    assert_eq!(host.recv(TIMEOUT), Ok(Message::Watch(None)));
+    host.send(&welcome(None)).unwrap();
+    poll_until(&mut client, |client| client.seat().is_none());
+
+    drop(host);
+    poll_until(&mut client, |client| !client.is_connected());
+    assert!(!client.is_reconnecting());
+    assert_eq!(client.side(), None);
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use chess::rules::{
+    clock::{Clock, Delay, TimeControl},
+    Side,
+};
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn time_controls_are_parsed() {
+    assert_eq!(TimeControl::parse("5+3"), Ok(TimeControl::new(5, 3)));
+    assert_eq!(
+        TimeControl::parse("5d2"),
+        Ok(TimeControl::new(5, 0).with_delay(Delay::Simple(secs(2))))
+    );
+    assert_eq!(
+        TimeControl::parse("15b10"),
+        Ok(TimeControl::new(15, 0).with_delay(Delay::Bronstein(secs(10))))
+    );
+    assert!(TimeControl::parse("0+1").is_err());
+    assert!(TimeControl::parse("fast").is_err());
+}
+
+#[test]
+fn pressing_the_clock_adds_the_increment_and_switches_sides() {
+    let mut clock = Clock::new(Some(TimeControl::new(1, 2)));
+    clock.run(Some(Side::White));
+
+    assert_eq!(clock.tick(secs(10)), None);
+    clock.press(Side::White);
+
+    assert_eq!(clock.time_left(Side::White), Some(secs(52)));
+    assert_eq!(clock.running(), Some(Side::Black));
+
+    clock.tick(secs(5));
+    assert_eq!(clock.time_left(Side::Black), Some(secs(55)));
+    assert_eq!(clock.time_left(Side::White), Some(secs(52)));
+}
+
+#[test]
+fn the_first_move_earns_its_increment_before_the_clock_runs() {
+    let mut clock = Clock::new(Some(TimeControl::new(1, 2)));
+    clock.press(Side::White);
+
+    assert_eq!(clock.time_left(Side::White), Some(secs(62)));
+    assert_eq!(clock.running(), Some(Side::Black));
+
+    // a side can not press the clock while the other side's time runs
+    clock.press(Side::White);
+    assert_eq!(clock.time_left(Side::White), Some(secs(62)));
+}
+
+#[test]
+fn a_us_delay_passes_before_the_time_counts_down() {
+    let control = TimeControl::new(1, 0).with_delay(Delay::Simple(secs(3)));
+    let mut clock = Clock::new(Some(control));
+    clock.run(Some(Side::White));
+
+    clock.tick(secs(2));
+    assert_eq!(clock.time_left(Side::White), Some(secs(60)));
+
+    clock.tick(secs(3));
+    assert_eq!(clock.time_left(Side::White), Some(secs(58)));
+}
+
+#[test]
+fn a_bronstein_delay_gives_back_the_time_used() {
+    let control = TimeControl::new(1, 0).with_delay(Delay::Bronstein(secs(3)));
+    let mut clock = Clock::new(Some(control));
+
+    clock.run(Some(Side::White));
+    clock.tick(secs(2));
+    clock.press(Side::White);
+    assert_eq!(clock.time_left(Side::White), Some(secs(60)));
+
+    clock.run(Some(Side::White));
+    clock.tick(secs(10));
+    clock.press(Side::White);
+    assert_eq!(clock.time_left(Side::White), Some(secs(53)));
+}
+
+#[test]
+fn the_flag_falls_when_the_time_runs_out() {
+    let mut clock = Clock::new(Some(TimeControl::new(1, 0)));
+    clock.run(Some(Side::Black));
+
+    assert_eq!(clock.tick(secs(59)), None);
+    assert_eq!(clock.tick(secs(2)), Some(Side::Black));
+    assert_eq!(clock.time_left(Side::Black), Some(Duration::ZERO));
+    assert_eq!(clock.running(), None);
+}
+
+#[test]
+fn an_untimed_clock_never_runs_out() {
+    let mut clock = Clock::new(None);
+    clock.run(Some(Side::White));
+
+    assert_eq!(clock.tick(secs(100_000)), None);
+    assert_eq!(clock.time_left(Side::White), None);
+}
+
+#[test]
+fn time_controls_are_written_the_way_they_are_read() {
+    let controls = [
+        ("5+3", "5 min + 3s"),
+        ("1+0", "1 min + 0s"),
+        ("5d2", "5 min, 2s US delay"),
+        ("15b10", "15 min, 10s Bronstein delay"),
+    ];
+
+    for (text, name) in controls {
+        let control = TimeControl::parse(text).unwrap();
+        assert_eq!(control.notation(), text);
+        assert_eq!(control.to_string(), name);
+        assert!(TimeControl::parse(name).is_err());
+    }
+}
+
+#[test]
+fn a_clock_can_follow_one_kept_somewhere_else() {
+    let mut clock = Clock::new(None);
+    let control = TimeControl::new(5, 0);
+
+    clock.follow(Some(control), secs(200), secs(100), Some(Side::Black));
+    assert_eq!(clock.control(), Some(control));
+    assert_eq!(clock.running(), Some(Side::Black));
+
+    clock.tick(secs(10));
+    assert_eq!(clock.time_left(Side::Black), Some(secs(90)));
+
+    // the time spent so far is counted by the times sent
+    clock.follow(Some(control), secs(200), secs(95), Some(Side::Black));
+    assert_eq!(clock.time_left(Side::Black), Some(secs(95)));
+    assert_eq!(clock.time_left(Side::White), Some(secs(200)));
+
+    // a paused game stops the clock
+    clock.follow(Some(control), secs(200), secs(95), None);
+    clock.tick(secs(10));
+    assert_eq!(clock.time_left(Side::Black), Some(secs(95)));
+}
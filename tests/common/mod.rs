@@ -0,0 +1,33 @@
+//! Helpers shared by the tests, each test file uses some of them
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use chess::{
+    net::NetMove,
+    rules::{BoardPos, Position},
+};
+
+/// How long to wait for anything sent over the network
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn square(name: &str) -> BoardPos {
+    BoardPos::from_algebraic(name).unwrap()
+}
+
+/// A move that is not a promotion, as it is sent over the network
+pub fn net_move(start: &str, click: &str) -> NetMove {
+    NetMove {
+        start: square(start),
+        click: square(click),
+        promotion: None,
+    }
+}
+
+/// Plays moves written like `e2e4`
+pub fn play(position: &mut Position, moves: &[&str]) {
+    for text in moves {
+        let change = position.parse_uci_move(text).unwrap();
+        position.apply_change(change);
+    }
+}
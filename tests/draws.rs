@@ -0,0 +1,153 @@
+use chess::rules::{DrawReason, GameStatus, Position};
+
+mod common;
+
+use common::play;
+
+const KNIGHT_DANCE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+#[test]
+fn threefold_repetition_can_be_claimed() {
+    let mut position = Position::new();
+    play(&mut position, &KNIGHT_DANCE);
+    assert_eq!(position.repetitions(), 2);
+    assert_eq!(position.claimable_draw(), None);
+
+    play(&mut position, &KNIGHT_DANCE);
+    assert_eq!(position.repetitions(), 3);
+    assert_eq!(
+        position.claimable_draw(),
+        Some(DrawReason::ThreefoldRepetition)
+    );
+    assert_eq!(position.game_status(), GameStatus::Ongoing);
+
+    assert_eq!(position.claim_draw(), Some(DrawReason::ThreefoldRepetition));
+    assert_eq!(
+        position.game_status(),
+        GameStatus::Draw {
+            reason: DrawReason::ThreefoldRepetition
+        }
+    );
+    assert_eq!(position.result(), "1/2-1/2");
+}
+
+#[test]
+fn a_double_push_that_can_not_be_taken_en_passant_still_repeats() {
+    let mut position = Position::new();
+    play(&mut position, &["e2e4"]);
+    play(&mut position, &["g8f6", "g1f3", "f6g8", "f3g1"]);
+    assert_eq!(position.repetitions(), 2);
+    play(&mut position, &["g8f6", "g1f3", "f6g8", "f3g1"]);
+    assert_eq!(
+        position.claimable_draw(),
+        Some(DrawReason::ThreefoldRepetition)
+    );
+
+    // with a pawn that could take, the position after the push was different
+    let mut position = Position::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+    play(&mut position, &["e2e4"]);
+    play(&mut position, &["e8d8", "e1d1", "d8e8", "d1e1"]);
+    assert_eq!(position.repetitions(), 1);
+}
+
+#[test]
+fn fivefold_repetition_ends_the_game() {
+    let mut position = Position::new();
+    for _ in 0..4 {
+        play(&mut position, &KNIGHT_DANCE);
+    }
+
+    assert_eq!(
+        position.game_status(),
+        GameStatus::Draw {
+            reason: DrawReason::FivefoldRepetition
+        }
+    );
+}
+
+#[test]
+fn undo_takes_back_a_claim() {
+    let mut position = Position::new();
+    play(&mut position, &KNIGHT_DANCE);
+    play(&mut position, &KNIGHT_DANCE);
+    position.claim_draw();
+
+    position.undo();
+    assert_eq!(position.game_status(), GameStatus::Ongoing);
+}
+
+#[test]
+fn pawn_moves_and_captures_reset_the_clock() {
+    let mut position = Position::new();
+    play(&mut position, &["g1f3", "g8f6"]);
+    assert_eq!(position.halfmove_clock(), 2);
+
+    play(&mut position, &["e2e4"]);
+    assert_eq!(position.halfmove_clock(), 0);
+
+    play(&mut position, &["f6e4"]);
+    assert_eq!(position.halfmove_clock(), 0);
+}
+
+#[test]
+fn fifty_move_rule() {
+    let mut position = Position::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+    assert_eq!(position.claimable_draw(), None);
+
+    play(&mut position, &["a1a2"]);
+    assert_eq!(position.claimable_draw(), Some(DrawReason::FiftyMoves));
+    assert_eq!(position.game_status(), GameStatus::Ongoing);
+}
+
+#[test]
+fn seventy_five_move_rule() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 150 80").unwrap();
+
+    assert_eq!(
+        position.game_status(),
+        GameStatus::Draw {
+            reason: DrawReason::SeventyFiveMoves
+        }
+    );
+}
+
+#[test]
+fn checkmate_beats_the_seventy_five_move_rule() {
+    let position = Position::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 80").unwrap();
+
+    assert!(matches!(
+        position.game_status(),
+        GameStatus::Checkmate { .. }
+    ));
+}
+
+#[test]
+fn insufficient_material() {
+    let drawn = [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+        "2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1",
+    ];
+    for fen in drawn {
+        let position = Position::from_fen(fen).unwrap();
+        assert!(position.has_insufficient_material(), "{fen}");
+        assert_eq!(
+            position.game_status(),
+            GameStatus::Draw {
+                reason: DrawReason::InsufficientMaterial
+            }
+        );
+    }
+
+    let playable = [
+        "4k3/8/8/8/8/8/8/2BBK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+        "3bk3/8/8/8/8/8/8/3BK3 w - - 0 1",
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+    ];
+    for fen in playable {
+        let position = Position::from_fen(fen).unwrap();
+        assert!(!position.has_insufficient_material(), "{fen}");
+    }
+}
@@ -0,0 +1,106 @@
+#![cfg(unix)]
+
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use chess::ai::external::ExternalEngine;
+use chess::rules::{Position, Variant};
+
+mod common;
+
+use common::play;
+
+const ENGINE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/scripted_engine.sh"
+);
+const MOVE_TIME: Duration = Duration::from_millis(100);
+
+fn standard_game(moves: &[&str]) -> Position {
+    let mut position = Position::new().with_variant(Variant::Standard);
+    play(&mut position, moves);
+    position
+}
+
+#[test]
+fn handshake() {
+    let engine = ExternalEngine::start(ENGINE).unwrap();
+
+    assert_eq!(engine.name(), "Scripted engine");
+    assert!(engine.has_option("Hash"));
+    assert!(!engine.has_option("RookPush"));
+}
+
+#[test]
+fn plays_the_engine_replies() {
+    let mut engine = ExternalEngine::start(ENGINE).unwrap();
+    let stop = AtomicBool::new(false);
+
+    let mut position = standard_game(&["e2e4"]);
+    let reply = engine
+        .best_move(&position, MOVE_TIME, &stop)
+        .unwrap()
+        .unwrap();
+    assert_eq!(reply.to_uci(), "e7e5");
+
+    position.apply_change(reply);
+    play(&mut position, &["g1f3"]);
+
+    let reply = engine
+        .best_move(&position, MOVE_TIME, &stop)
+        .unwrap()
+        .unwrap();
+    assert_eq!(reply.to_uci(), "b8c6");
+}
+
+#[test]
+fn sends_the_moves_since_the_start() {
+    let position = standard_game(&["e2e4", "e7e5"]);
+
+    assert_eq!(
+        position.to_uci_position(),
+        "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves e2e4 e7e5"
+    );
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let mut engine = ExternalEngine::start(ENGINE).unwrap();
+    let stop = AtomicBool::new(false);
+
+    let position = standard_game(&["d2d4"]);
+    assert!(engine.best_move(&position, MOVE_TIME, &stop).is_err());
+}
+
+#[test]
+fn no_move_once_stopped() {
+    let mut engine = ExternalEngine::start(ENGINE).unwrap();
+    let stop = AtomicBool::new(true);
+
+    let position = standard_game(&["c2c4"]);
+    assert_eq!(engine.best_move(&position, MOVE_TIME, &stop), Ok(None));
+}
+
+#[test]
+fn missing_engine() {
+    assert!(ExternalEngine::start("/does/not/exist").is_err());
+}
+
+#[test]
+fn plays_against_our_own_engine() {
+    let mut engine = ExternalEngine::start(env!("CARGO_BIN_EXE_uci")).unwrap();
+    let stop = AtomicBool::new(false);
+    assert!(engine.has_option("RookPush"));
+    engine.set_option("RookPush", "true").unwrap();
+
+    let mut position = Position::new();
+    for _ in 0..4 {
+        let change = engine
+            .best_move(&position, MOVE_TIME, &stop)
+            .unwrap()
+            .unwrap();
+        position.apply_change(change);
+    }
+
+    assert_eq!(position.history().len(), 4);
+}
@@ -0,0 +1,99 @@
+use chess::rules::{BoardPos, PieceType, Position, Side, START_FEN};
+
+#[test]
+fn positions_are_written_back_the_way_they_were_read() {
+    let fens = [
+        START_FEN,
+        // castling rights on one side only, and the clocks
+        "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 12 40",
+        "r3k2r/8/8/8/8/8/8/R3K2R b - - 0 1",
+        // en passant for either side
+        "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+        "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 3",
+    ];
+
+    for fen in fens {
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn the_move_counters_can_be_left_out() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn a_pawn_can_be_taken_en_passant_from_a_fen() {
+    let fen = "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 3";
+    let mut position = Position::from_fen(fen).unwrap();
+
+    let change = position.parse_uci_move("d5e6").unwrap();
+    position.apply_change(change);
+    let taken = BoardPos::from_algebraic("e5").unwrap();
+    assert!(position.get_piece(taken).is_none());
+
+    let pawn = position.get_piece(BoardPos::from_algebraic("e6").unwrap());
+    assert!(pawn.is_some_and(|pawn| pawn.piece_type == PieceType::Pawn && pawn.side == Side::White));
+}
+
+#[test]
+fn invalid_fens_are_rejected() {
+    let invalid = [
+        (
+            "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "Rank 7 does not have 8 squares",
+        ),
+        (
+            "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "Rank 7 does not have 8 squares",
+        ),
+        (
+            "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1",
+            "Expected one Black king, found 0",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKKBNR w kq - 0 1",
+            "Expected one White king, found 2",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1",
+            "Invalid castling rights: KQkx",
+        ),
+        // en passant squares on the wrong rank for the side to move
+        (
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e4 0 3",
+            "Invalid en passant square: e4",
+        ),
+        (
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 3",
+            "Invalid en passant square: e3",
+        ),
+        // en passant squares without a pawn of the side that just moved in front
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1",
+            "No pawn to take en passant on e3",
+        ),
+        (
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq d3 0 3",
+            "No pawn to take en passant on d3",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w",
+            "Expected 4 to 6 fields in FEN, found 2",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "Invalid side to move: x",
+        ),
+    ];
+
+    for (fen, error) in invalid {
+        assert_eq!(
+            Position::from_fen(fen).err().as_deref(),
+            Some(error),
+            "{}",
+            fen
+        );
+    }
+}
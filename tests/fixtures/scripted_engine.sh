@@ -0,0 +1,27 @@
+#!/bin/sh
+# A stand-in UCI engine for tests, it answers each position with a move from the script below
+position=""
+
+while read -r line; do
+    case "$line" in
+        uci)
+            echo "id name Scripted engine"
+            echo "option name Hash type spin default 1 min 1 max 1"
+            echo "uciok"
+            ;;
+        isready) echo "readyok" ;;
+        position*) position="$line" ;;
+        go*)
+            case "$position" in
+                *"moves e2e4 e7e5 g1f3") echo "bestmove b8c6" ;;
+                *"moves e2e4") echo "info depth 1 score cp 0"; echo "bestmove e7e5" ;;
+                *"moves d2d4") echo "bestmove e1e8" ;;
+                # only answers once it is told to stop
+                *"moves c2c4") ;;
+                *) echo "bestmove 0000" ;;
+            esac
+            ;;
+        stop) echo "bestmove e7e5" ;;
+        quit) exit 0 ;;
+    esac
+done
@@ -0,0 +1,69 @@
+use chess::rules::{BoardPos, DrawReason, GameStatus, Position, Side};
+
+#[test]
+fn resigning_loses_the_game() {
+    let mut position = Position::new();
+    position.resign(Side::White);
+
+    assert_eq!(
+        position.game_status(),
+        GameStatus::Resignation {
+            winner: Side::Black
+        }
+    );
+    assert_eq!(position.result(), "0-1");
+}
+
+#[test]
+fn running_out_of_time_loses_the_game() {
+    let mut position = Position::new();
+    position.time_out(Side::Black);
+
+    assert_eq!(
+        position.game_status(),
+        GameStatus::Timeout {
+            winner: Side::White
+        }
+    );
+    assert_eq!(position.result(), "1-0");
+}
+
+#[test]
+fn running_out_of_time_against_a_lone_knight_is_a_draw() {
+    let mut position = Position::from_fen("4k3/pppp4/8/8/8/8/8/1N2K3 b - - 0 1").unwrap();
+    position.time_out(Side::Black);
+
+    assert_eq!(
+        position.game_status(),
+        GameStatus::Draw {
+            reason: DrawReason::TimeoutVsInsufficientMaterial
+        }
+    );
+}
+
+#[test]
+fn undo_takes_back_a_resignation() {
+    let mut position = Position::new();
+    let change = position.parse_uci_move("e2e4").unwrap();
+    position.apply_change(change);
+    position.resign(Side::Black);
+
+    position.undo();
+    assert_eq!(position.game_status(), GameStatus::Ongoing);
+}
+
+#[test]
+fn the_king_in_check_is_found() {
+    let mut position = Position::new();
+    assert_eq!(position.checked_king(), None);
+
+    for text in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+        let change = position.parse_uci_move(text).unwrap();
+        position.apply_change(change);
+    }
+
+    assert_eq!(
+        position.checked_king(),
+        Some(BoardPos::from_algebraic("e1").unwrap())
+    );
+}
@@ -0,0 +1,265 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{
+    net::{Connection, Event, Host, Message},
+    rules::{Position, Side, TimeControl, Variant},
+};
+
+mod common;
+
+use common::{net_move, TIMEOUT};
+
+/// A game hosted by white, played without a window. The position stands in for
+/// the window's board
+struct HostedGame {
+    host: Host,
+    position: Position,
+    control: Option<TimeControl>,
+    events: Vec<Event>,
+}
+
+impl HostedGame {
+    fn new() -> Self {
+        Self {
+            host: Host::bind("127.0.0.1:0", Side::White).unwrap(),
+            position: Position::new().with_variant(Variant::Standard),
+            control: None,
+            events: Vec::new(),
+        }
+    }
+
+    fn address(&self) -> String {
+        self.host.local_addr().unwrap()
+    }
+
+    /// Lets the host handle everything that has arrived, like the window does
+    /// every frame
+    fn step(&mut self) {
+        self.host.catch_up(&self.position, self.control);
+        for event in self.host.step() {
+            match &event {
+                Event::Move { change, .. } => {
+                    self.position.apply_change(change.clone());
+                }
+                Event::Resign(side) | Event::Abandoned(side) => self.position.resign(*side),
+                Event::Timeout(side) => self.position.time_out(*side),
+            }
+            self.events.push(event);
+        }
+        assert_eq!(
+            self.host.game().position().to_uci_position(),
+            self.position.to_uci_position()
+        );
+    }
+
+    /// Plays a move for white on the host
+    fn play(&mut self, start: &str, click: &str) {
+        let change = net_move(start, click)
+            .validate(&self.position, Side::White)
+            .unwrap();
+        self.position.apply_change(change);
+    }
+
+    /// The next message to `connection` that is not just for reading, about the
+    /// clock or a seat
+    fn next(&mut self, connection: &mut Connection) -> Message {
+        self.next_matching(connection, |message| {
+            !matches!(
+                message,
+                Message::Notice(_) | Message::Clock { .. } | Message::Seat { .. }
+            )
+        })
+    }
+
+    fn next_matching(
+        &mut self,
+        connection: &mut Connection,
+        wanted: impl Fn(&Message) -> bool,
+    ) -> Message {
+        let started = Instant::now();
+        while started.elapsed() < TIMEOUT {
+            self.step();
+            while let Some(message) = connection.poll() {
+                let message = message.unwrap();
+                if wanted(&message) {
+                    return message;
+                }
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("Nothing arrived in time");
+    }
+
+    /// Steps the host until it has seen `count` events
+    fn wait_for_events(&mut self, count: usize) {
+        let started = Instant::now();
+        while self.events.len() < count {
+            assert!(started.elapsed() < TIMEOUT, "Nothing happened in time");
+            self.step();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+/// Joins the game as black and returns the connection and the message that takes
+/// the seat back
+fn join(game: &mut HostedGame) -> (Connection, Message) {
+    let mut guest = Connection::connect(&game.address()).unwrap();
+    guest.send(&Message::Play).unwrap();
+
+    let resume = match game.next_matching(&mut guest, |message| {
+        matches!(message, Message::Seat { .. })
+    }) {
+        Message::Seat { game, token, .. } => Message::Resume { game, token },
+        _ => unreachable!(),
+    };
+    match game.next(&mut guest) {
+        Message::Welcome { side, .. } => assert_eq!(side, Some(Side::Black)),
+        message => panic!("Expected a welcome, got {}", message),
+    }
+    (guest, resume)
+}
+
+#[test]
+fn the_guest_plays_black_and_only_legal_moves_are_played() {
+    let mut game = HostedGame::new();
+    let (mut guest, _) = join(&mut game);
+
+    // out of turn
+    guest.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert!(matches!(game.next(&mut guest), Message::Reject(_)));
+
+    game.play("e2", "e4");
+    assert_eq!(game.next(&mut guest), Message::Move(net_move("e2", "e4")));
+
+    guest.send(&Message::Move(net_move("e7", "e4"))).unwrap();
+    assert!(matches!(game.next(&mut guest), Message::Reject(_)));
+    guest.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert_eq!(game.next(&mut guest), Message::Move(net_move("e7", "e5")));
+    assert!(game.position.to_uci_position().ends_with("moves e2e4 e7e5"));
+
+    // the seat is taken, but anyone can watch
+    let mut other = Connection::connect(&game.address()).unwrap();
+    other.send(&Message::Play).unwrap();
+    assert!(matches!(game.next(&mut other), Message::Reject(_)));
+    other.send(&Message::Watch(None)).unwrap();
+    match game.next(&mut other) {
+        Message::Welcome { side, game, .. } => {
+            assert_eq!(side, None);
+            assert!(game.ends_with("moves e2e4 e7e5"), "{}", game);
+        }
+        message => panic!("Expected a welcome, got {}", message),
+    }
+
+    game.play("g1", "f3");
+    assert_eq!(game.next(&mut other), Message::Move(net_move("g1", "f3")));
+    assert_eq!(game.next(&mut guest), Message::Move(net_move("g1", "f3")));
+}
+
+#[test]
+fn a_guest_who_loses_the_connection_can_come_back() {
+    let mut game = HostedGame::new();
+    let (mut guest, resume) = join(&mut game);
+
+    game.play("e2", "e4");
+    assert_eq!(game.next(&mut guest), Message::Move(net_move("e2", "e4")));
+
+    drop(guest);
+    let started = Instant::now();
+    while !game.host.is_away(Side::Black) {
+        assert!(started.elapsed() < TIMEOUT, "The guest was not missed");
+        game.step();
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    // someone else can not take the seat meanwhile
+    let mut other = Connection::connect(&game.address()).unwrap();
+    other.send(&Message::Play).unwrap();
+    assert!(matches!(game.next(&mut other), Message::Reject(_)));
+
+    let mut guest = Connection::connect(&game.address()).unwrap();
+    guest.send(&resume).unwrap();
+    match game.next(&mut guest) {
+        Message::Welcome { side, game, .. } => {
+            assert_eq!(side, Some(Side::Black));
+            assert!(game.ends_with("moves e2e4"), "{}", game);
+        }
+        message => panic!("Expected a welcome, got {}", message),
+    }
+    assert!(!game.host.is_away(Side::Black));
+
+    guest.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert_eq!(game.next(&mut guest), Message::Move(net_move("e7", "e5")));
+}
+
+#[test]
+fn a_guest_who_does_not_come_back_loses() {
+    let mut game = HostedGame::new();
+    game.host = Host::bind("127.0.0.1:0", Side::White)
+        .unwrap()
+        .with_grace_period(Duration::from_millis(200));
+    let (guest, resume) = join(&mut game);
+    let mut spectator = Connection::connect(&game.address()).unwrap();
+    spectator.send(&Message::Watch(None)).unwrap();
+    assert!(matches!(game.next(&mut spectator), Message::Welcome { .. }));
+
+    drop(guest);
+    game.wait_for_events(1);
+    assert_eq!(game.events, [Event::Abandoned(Side::Black)]);
+    assert_eq!(game.next(&mut spectator), Message::Resign(Side::Black));
+
+    // the game is over, but it can still be looked at from the seat
+    let mut guest = Connection::connect(&game.address()).unwrap();
+    guest.send(&resume).unwrap();
+    assert!(matches!(game.next(&mut guest), Message::Welcome { .. }));
+    assert_eq!(game.next(&mut guest), Message::Resign(Side::Black));
+}
+
+#[test]
+fn the_guest_hears_about_the_host_resigning_and_a_new_game() {
+    let mut game = HostedGame::new();
+    let (mut guest, _) = join(&mut game);
+
+    game.play("e2", "e4");
+    assert_eq!(game.next(&mut guest), Message::Move(net_move("e2", "e4")));
+    game.position.resign(Side::White);
+    assert_eq!(game.next(&mut guest), Message::Resign(Side::White));
+
+    game.position = Position::new().with_variant(Variant::Standard);
+    match game.next(&mut guest) {
+        Message::Welcome { side, game, .. } => {
+            assert_eq!(side, Some(Side::Black));
+            assert!(!game.contains("moves"), "{}", game);
+        }
+        message => panic!("Expected a welcome, got {}", message),
+    }
+}
+
+#[test]
+fn the_moves_made_on_the_host_are_played_on_its_clock() {
+    let mut game = HostedGame::new();
+    game.control = TimeControl::parse("1+2").ok();
+    let (mut guest, _) = join(&mut game);
+
+    game.play("e2", "e4");
+    assert_eq!(game.next(&mut guest), Message::Move(net_move("e2", "e4")));
+    // the first move earns its increment, and black's time has started
+    let clock = game.next_matching(&mut guest, |message| {
+        matches!(message, Message::Clock { .. })
+    });
+    match clock {
+        Message::Clock {
+            control,
+            white,
+            running,
+            ..
+        } => {
+            assert_eq!(control, game.control);
+            assert_eq!(white, Duration::from_secs(62));
+            assert_eq!(running, Some(Side::Black));
+        }
+        message => panic!("Expected the clock, got {}", message),
+    }
+    assert_eq!(game.host.game().clock().running(), Some(Side::Black));
+}
@@ -0,0 +1,53 @@
+use chess::rules::{pgn::MovePair, Position};
+
+mod common;
+
+use common::play;
+
+#[test]
+fn moves_are_paired_by_number() {
+    let mut position = Position::new();
+    play(&mut position, &["e2e4", "e7e5", "g1f3"]);
+
+    assert_eq!(
+        position.move_pairs(),
+        [
+            MovePair {
+                number: 1,
+                white: Some(0),
+                black: Some(1),
+            },
+            MovePair {
+                number: 2,
+                white: Some(2),
+                black: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn a_game_starting_with_black_has_no_first_white_move() {
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 7";
+    let mut position = Position::from_fen(fen).unwrap();
+    play(&mut position, &["e7e5", "g1f3"]);
+
+    assert_eq!(
+        position.move_pairs(),
+        [
+            MovePair {
+                number: 7,
+                white: None,
+                black: Some(0),
+            },
+            MovePair {
+                number: 8,
+                white: Some(1),
+                black: None,
+            },
+        ]
+    );
+    assert!(position
+        .to_pgn(&Default::default())
+        .contains("7... e5 8. Nf3 *"));
+}
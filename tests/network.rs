@@ -0,0 +1,271 @@
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{
+    net::{new_token, Connection, Message, NetMove},
+    rules::{PieceType, Position, Side, TimeControl, Variant},
+};
+
+mod common;
+
+use common::{net_move, play, TIMEOUT};
+
+#[test]
+fn messages_are_read_back_the_way_they_are_written() {
+    let messages = [
+        Message::Play,
+        Message::Watch(None),
+        Message::Watch(Some(3)),
+        Message::Welcome {
+            side: Some(Side::Black),
+            variant: Variant::RookPush,
+            game: Position::new().to_uci_position(),
+        },
+        Message::Welcome {
+            side: None,
+            variant: Variant::Standard,
+            game: Position::new().to_uci_position(),
+        },
+        Message::Clock {
+            control: TimeControl::parse("5d3").ok(),
+            white: Duration::from_millis(299_500),
+            black: Duration::from_secs(300),
+            running: Some(Side::White),
+        },
+        Message::Clock {
+            control: None,
+            white: Duration::ZERO,
+            black: Duration::ZERO,
+            running: None,
+        },
+        Message::Seat {
+            game: 2,
+            token: new_token(),
+            grace: Duration::from_secs(60),
+        },
+        Message::Resume {
+            game: 2,
+            token: "0123abcd".to_owned(),
+        },
+        Message::Timeout(Side::Black),
+        Message::Draw,
+        Message::Notice("Waiting for an opponent".to_owned()),
+        Message::Move(net_move("e2", "e4")),
+        Message::Move(NetMove {
+            promotion: Some(PieceType::Knight),
+            ..net_move("a7", "a8")
+        }),
+        Message::Reject("It is not Black's turn".to_owned()),
+        Message::Resign(Side::White),
+    ];
+
+    for message in messages {
+        assert_eq!(Message::parse(&message.to_string()), Ok(message));
+    }
+
+    assert_eq!(
+        Message::Move(net_move("g1", "f3")).to_string(),
+        "move g1 f3"
+    );
+    assert!(Message::parse("move e2").is_err());
+    assert!(Message::parse("move e2 e4 x").is_err());
+    assert!(Message::parse("resume 2").is_err());
+    assert!(Message::parse("hello").is_err());
+}
+
+#[test]
+fn endings_that_are_not_on_the_board_are_sent_after_the_moves() {
+    let mut position = Position::new().with_variant(Variant::Standard);
+    play(&mut position, &["e2e4", "e7e5"]);
+    assert_eq!(Message::ending(&position), None);
+
+    let mut resigned = position.clone();
+    resigned.resign(Side::Black);
+    assert_eq!(
+        Message::ending(&resigned),
+        Some(Message::Resign(Side::Black))
+    );
+
+    let mut flagged = position.clone();
+    flagged.time_out(Side::White);
+    assert_eq!(
+        Message::ending(&flagged),
+        Some(Message::Timeout(Side::White))
+    );
+
+    // the starting position a third time
+    let mut repeated = Position::new().with_variant(Variant::Standard);
+    let knights = ["g1f3", "g8f6", "f3g1", "f6g8"];
+    play(&mut repeated, &knights);
+    play(&mut repeated, &knights);
+    assert_eq!(Message::ending(&repeated), None);
+    repeated.claim_draw().unwrap();
+    assert_eq!(Message::ending(&repeated), Some(Message::Draw));
+
+    // checkmate can be seen from the moves
+    let mut mated = Position::new().with_variant(Variant::Standard);
+    play(&mut mated, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+    assert!(mated.game_status().is_over());
+    assert_eq!(Message::ending(&mated), None);
+}
+
+#[test]
+fn every_legal_move_can_be_sent() {
+    let fens = [
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        // a rook next to pieces it can push, and a pawn about to promote
+        "4k3/1P6/8/8/8/8/R1N5/4K3 w - - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ];
+
+    for fen in fens {
+        let position = Position::from_fen(fen).unwrap();
+        let side = position.turn();
+
+        for change in position.get_all_possible_moves() {
+            let sent = NetMove::from_change(&change);
+            assert_eq!(sent.validate(&position, side), Ok(change), "{}", fen);
+        }
+    }
+}
+
+#[test]
+fn illegal_and_out_of_turn_moves_are_rejected() {
+    let mut position = Position::new();
+
+    assert!(net_move("e7", "e5")
+        .validate(&position, Side::Black)
+        .is_err());
+    assert!(net_move("e7", "e5")
+        .validate(&position, Side::White)
+        .is_err());
+    assert!(net_move("e3", "e4")
+        .validate(&position, Side::White)
+        .is_err());
+    assert!(net_move("e2", "e5")
+        .validate(&position, Side::White)
+        .is_err());
+    assert!(net_move("e2", "e4")
+        .validate(&position, Side::White)
+        .is_ok());
+
+    let promotion = NetMove {
+        promotion: Some(PieceType::Queen),
+        ..net_move("e2", "e4")
+    };
+    assert!(promotion.validate(&position, Side::White).is_err());
+
+    play(&mut position, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+    assert!(net_move("e2", "e4")
+        .validate(&position, Side::White)
+        .is_err());
+}
+
+#[test]
+fn a_promotion_needs_a_piece_to_promote_to() {
+    let position = Position::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+    assert!(net_move("b7", "b8")
+        .validate(&position, Side::White)
+        .is_err());
+
+    for (piece_type, ok) in [(PieceType::Knight, true), (PieceType::King, false)] {
+        let promotion = NetMove {
+            promotion: Some(piece_type),
+            ..net_move("b7", "b8")
+        };
+        assert_eq!(promotion.validate(&position, Side::White).is_ok(), ok);
+    }
+}
+
+#[test]
+fn the_game_so_far_is_sent_as_a_uci_position() {
+    let mut position = Position::new().with_variant(Variant::Standard);
+    play(&mut position, &["e2e4", "e7e5", "g1f3"]);
+
+    let game = position.to_uci_position();
+    let received = Position::from_uci_position(&game, Variant::Standard).unwrap();
+
+    assert_eq!(received.to_fen(), position.to_fen());
+    assert_eq!(received.history().len(), 3);
+    assert_eq!(received.variant(), Variant::Standard);
+    assert!(Position::from_uci_position("position moves e2e4", Variant::Standard).is_err());
+}
+
+#[test]
+fn moves_are_sent_over_loopback() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let mut guest = Connection::connect(&address).unwrap();
+    let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+
+    guest.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert_eq!(host.recv(TIMEOUT), Ok(Message::Move(net_move("e2", "e4"))));
+
+    host.send(&Message::Reject("It is not White's turn".to_owned()))
+        .unwrap();
+    assert_eq!(
+        guest.recv(TIMEOUT),
+        Ok(Message::Reject("It is not White's turn".to_owned()))
+    );
+    assert!(guest.poll().is_none());
+
+    drop(host);
+    assert!(guest.recv(TIMEOUT).is_err());
+    assert!(!guest.is_open());
+}
+
+#[test]
+fn a_peer_that_does_not_read_is_given_up_on() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    // the other end never reads, so its buffers fill up and then the queue does
+    let _stalled = Connection::connect(&address).unwrap();
+    let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+    let notice = Message::Notice("x".repeat(10_000));
+
+    let started = Instant::now();
+    let error = loop {
+        if let Err(err) = host.send(&notice) {
+            break err;
+        }
+        assert!(started.elapsed() < TIMEOUT * 2, "sending never failed");
+    };
+
+    assert!(error.contains("not keeping up") || error.contains("disconnected"));
+    assert!(!host.is_open());
+    assert!(host.send(&Message::Play).is_err());
+}
+
+#[test]
+fn a_peer_that_sends_too_much_is_given_up_on() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    // one line that never ends
+    let mut endless = TcpStream::connect(&address).unwrap();
+    let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+    let _ = endless.write_all(&[b'x'; 100_000]);
+    assert!(host.recv(TIMEOUT).is_err());
+    assert!(!host.is_open());
+
+    // more lines than are ever read
+    let mut flood = TcpStream::connect(&address).unwrap();
+    let host = Connection::new(listener.accept().unwrap().0).unwrap();
+    let started = Instant::now();
+    while host.is_open() {
+        assert!(started.elapsed() < TIMEOUT, "the flood was never noticed");
+        if flood.write_all(b"play\n").is_err() {
+            break;
+        }
+    }
+    let started = Instant::now();
+    while host.is_open() {
+        assert!(started.elapsed() < TIMEOUT, "the flood was never noticed");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
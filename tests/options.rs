@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use chess::net::{NetOptions, DEFAULT_PORT};
+use chess::rules::{TimeControl, Variant};
+
+/// Reads the arguments like the programs do, with anything unwanted sent back
+fn read(wanted: &[&str], line: &str) -> Result<(NetOptions, Vec<String>), String> {
+    let mut options = NetOptions::default();
+    let mut others = Vec::new();
+    let mut args = line.split_whitespace().map(str::to_owned);
+    while let Some(arg) = args.next() {
+        if !options.read(wanted, &arg, &mut args)? {
+            others.push(arg);
+        }
+    }
+    Ok((options, others))
+}
+
+#[test]
+fn network_options_are_read_with_their_values() {
+    let wanted = ["--join", "--resume", "--variant", "--time", "--grace"];
+    let (options, others) = read(
+        &wanted,
+        "--join example.com --fen --resume 3:0123abcd --variant standard --time 5+3 --grace 10",
+    )
+    .unwrap();
+
+    assert_eq!(
+        options.join.as_deref(),
+        Some(format!("example.com:{}", DEFAULT_PORT).as_str())
+    );
+    assert!(!options.watch);
+    assert_eq!(options.resume, Some((3, "0123abcd".to_owned())));
+    assert_eq!(options.variant, Variant::Standard);
+    assert_eq!(options.time_control, Some(TimeControl::new(5, 3)));
+    assert_eq!(options.grace, Duration::from_secs(10));
+    assert_eq!(others, ["--fen"]);
+}
+
+#[test]
+fn options_a_program_does_not_take_are_left_to_it() {
+    let (options, others) = read(&["--port"], "--port 0 --host 7000").unwrap();
+    assert_eq!(options.port, 0);
+    assert_eq!(options.host, None);
+    assert_eq!(others, ["--host", "7000"]);
+}
+
+#[test]
+fn a_bad_value_says_what_was_expected() {
+    assert_eq!(
+        read(&["--resume"], "--resume 3").err().as_deref(),
+        Some("--resume should be followed by the seat the host gave, like 3:9f2c41d07e6ab815")
+    );
+    assert_eq!(
+        read(&["--watch"], "--watch").err().as_deref(),
+        Some("--watch should be followed by the host's address")
+    );
+    assert!(read(&["--time"], "--time fast").is_err());
+}
@@ -0,0 +1,90 @@
+use chess::rules::{Position, Variant, START_FEN};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const WHITE_PUSH_PROMOTION: &str = "4k3/P7/8/8/8/8/8/R3K3 w - - 0 1";
+const BLACK_PUSH_PROMOTION: &str = "r3k3/8/8/8/8/8/p7/4K3 b - - 0 1";
+
+/// Checks the perft count at each depth, starting from depth 1
+fn assert_perft(fen: &str, variant: Variant, counts: &[u64]) {
+    let mut position = Position::from_fen(fen).unwrap().with_variant(variant);
+
+    for (depth, &expected) in (1..).zip(counts) {
+        assert_eq!(position.perft(depth), expected, "{fen} at depth {depth}");
+    }
+
+    assert_eq!(
+        position.to_fen(),
+        fen,
+        "perft should leave the position as it was"
+    );
+}
+
+mod standard {
+    use super::*;
+
+    #[test]
+    fn start_position() {
+        assert_perft(START_FEN, Variant::Standard, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, Variant::Standard, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, Variant::Standard, &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, Variant::Standard, &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, Variant::Standard, &[44, 1486, 62379]);
+    }
+}
+
+/// Counts recorded from our own move generator, there is nothing to compare
+/// them against so they only catch changes in behaviour
+mod rook_push {
+    use super::*;
+
+    #[test]
+    fn start_position() {
+        assert_perft(START_FEN, Variant::RookPush, &[22, 484, 11550, 274551]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, Variant::RookPush, &[51, 2271, 114611]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, Variant::RookPush, &[15, 220, 3425, 53295]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, Variant::RookPush, &[6, 276, 10757]);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, Variant::RookPush, &[46, 1690, 74687]);
+    }
+
+    #[test]
+    fn push_promotions() {
+        let counts = [22, 87, 2076, 11172];
+        assert_perft(WHITE_PUSH_PROMOTION, Variant::RookPush, &counts);
+        assert_perft(BLACK_PUSH_PROMOTION, Variant::RookPush, &counts);
+    }
+}
@@ -0,0 +1,124 @@
+use chess::rules::{pgn::PgnGame, BoardPos, PieceType, Position, Side, Variant};
+
+/// Plays a PGN game the way `--pgn` does, from its FEN tag if it has one
+fn import(pgn: &str) -> Result<Position, String> {
+    let game = PgnGame::parse(pgn)?;
+    let position = match game.tag("FEN") {
+        Some(fen) => Position::from_fen(fen)?,
+        None => Position::new(),
+    };
+
+    let mut position = position.with_variant(Variant::Standard);
+    for token in game.moves.iter() {
+        let change = position.resolve_san(token)?;
+        position.apply_change(change);
+    }
+    Ok(position)
+}
+
+fn piece_at(position: &Position, square: &str) -> Option<(PieceType, Side)> {
+    let piece = position.get_piece(BoardPos::from_algebraic(square).unwrap())?;
+    Some((piece.piece_type, piece.side))
+}
+
+#[test]
+fn a_game_is_read_past_everything_that_is_not_a_move() {
+    let pgn = r#"[Event "Club championship"]
+[White "Anderssen, \"The Immortal\""]
+[Black "Kieseritzky"]
+[Result "1-0"]
+
+% an escaped line that is not part of the game
+1. e4 {the king's pawn} e5 2.Nf3 $1 Nc6 ; the rest of this line is a comment
+3. Bb5!? (3. Bc4 Bc5 (3... Nf6) 4. c3) 3... a6 $6 4. Ba4 Nf6 5. O-O 1-0
+
+[Event "The next game"]
+
+1. d4 d5 *
+"#;
+
+    let game = PgnGame::parse(pgn).unwrap();
+    assert_eq!(game.tag("Event"), Some("Club championship"));
+    assert_eq!(game.tag("White"), Some("Anderssen, \"The Immortal\""));
+    assert_eq!(game.tag("Result"), Some("1-0"));
+    assert_eq!(
+        game.moves,
+        ["e4", "e5", "Nf3", "Nc6", "Bb5!?", "a6", "Ba4", "Nf6", "O-O"]
+    );
+
+    let position = import(pgn).unwrap();
+    assert_eq!(
+        position.to_fen(),
+        "r1bqkb1r/1ppp1ppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 3 5"
+    );
+}
+
+#[test]
+fn a_game_can_start_from_a_fen() {
+    let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/R3K3 b Q - 0 30"]
+
+30... Kd7 31. O-O-O+ Kc6 *
+"#;
+
+    let position = import(pgn).unwrap();
+    assert_eq!(
+        piece_at(&position, "c1"),
+        Some((PieceType::King, Side::White))
+    );
+    assert_eq!(
+        piece_at(&position, "d1"),
+        Some((PieceType::Rook, Side::White))
+    );
+    assert_eq!(position.to_fen(), "8/8/2k5/8/8/8/4P3/2KR4 w - - 3 32");
+}
+
+#[test]
+fn moves_that_two_pieces_could_make_say_which_piece() {
+    let position = import("1. d4 d5 2. Nf3 Nf6 3. Nbd2 *").unwrap();
+    assert_eq!(
+        piece_at(&position, "d2"),
+        Some((PieceType::Knight, Side::White))
+    );
+    assert_eq!(
+        piece_at(&position, "f3"),
+        Some((PieceType::Knight, Side::White))
+    );
+    assert_eq!(piece_at(&position, "b1"), None);
+
+    // saying which piece when only one could is fine too
+    let position = import("1. Ng1f3 Nbc6 *").unwrap();
+    assert_eq!(
+        piece_at(&position, "c6"),
+        Some((PieceType::Knight, Side::Black))
+    );
+}
+
+#[test]
+fn a_bad_move_is_reported_with_its_number() {
+    assert_eq!(
+        import("1. e4 e5 2. Ke3 *").err().as_deref(),
+        Some("Move 2. Ke3: no legal move matches")
+    );
+    assert_eq!(
+        import("1. e4 e5 2. Nf3 Kf6 *").err().as_deref(),
+        Some("Move 2... Kf6: no legal move matches")
+    );
+    assert_eq!(
+        import("1. d4 d5 2. Nf3 Nf6 3. Nd2 *").err().as_deref(),
+        Some("Move 3. Nd2: more than one legal move matches")
+    );
+    // there is no knight on the c-file to tell apart
+    assert_eq!(
+        import("1. d4 d5 2. Nf3 Nf6 3. Ncd2 *").err().as_deref(),
+        Some("Move 3. Ncd2: no legal move matches")
+    );
+}
+
+#[test]
+fn broken_movetext_is_rejected() {
+    assert!(PgnGame::parse("1. e4 {never closed").is_err());
+    assert!(PgnGame::parse("1. e4 (1. d4 e5").is_err());
+    assert!(PgnGame::parse("1. e4 e5) 2. Nf3").is_err());
+    assert!(PgnGame::parse("[Event Casual]\n1. e4").is_err());
+}
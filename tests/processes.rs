@@ -0,0 +1,200 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{net::Message, rules::Side};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One of the binaries, run with its output read on another thread. It is killed
+/// when dropped
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Process {
+    fn start(program: &str, args: &[&str]) -> Self {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            child,
+            stdin,
+            lines,
+        }
+    }
+
+    fn server(args: &[&str]) -> Self {
+        Self::start(env!("CARGO_BIN_EXE_server"), args)
+    }
+
+    fn client(args: &[&str]) -> Self {
+        Self::start(env!("CARGO_BIN_EXE_client"), args)
+    }
+
+    /// Types a line, like a message to send
+    fn type_line(&mut self, line: &str) {
+        writeln!(self.stdin, "{}", line).unwrap();
+    }
+
+    /// The first line printed that `wanted` picks something out of, skipping the rest
+    fn wait_for<T>(&mut self, wanted: impl Fn(&str) -> Option<T>) -> T {
+        let started = Instant::now();
+        while let Some(left) = TIMEOUT.checked_sub(started.elapsed()) {
+            match self.lines.recv_timeout(left) {
+                Ok(line) => {
+                    if let Some(found) = wanted(&line) {
+                        return found;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        panic!("Nothing wanted was printed in time");
+    }
+
+    /// The next message printed that `wanted` picks out
+    fn wait_for_message(&mut self, wanted: impl Fn(&Message) -> bool) -> Message {
+        self.wait_for(|line| Message::parse(line).ok().filter(|message| wanted(message)))
+    }
+
+    /// The address printed once listening, with the port that was picked
+    fn address(&mut self) -> String {
+        let port = self.wait_for(|line| {
+            let (_, address) = line.rsplit_once(" on ")?;
+            address.rsplit_once(':').map(|(_, port)| port.to_owned())
+        });
+        format!("127.0.0.1:{}", port)
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+fn is_move(message: &Message) -> bool {
+    matches!(message, Message::Move(_))
+}
+
+/// The time white has left on the next clock printed after a move
+fn white_time(process: &mut Process) -> Duration {
+    match process.wait_for_message(|message| matches!(message, Message::Clock { .. })) {
+        Message::Clock { white, .. } => white,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn a_player_whose_client_is_killed_gets_their_seat_and_clock_back() {
+    let mut server = Process::server(&["--port", "0", "--variant", "standard", "--time", "5+0"]);
+    let address = server.address();
+
+    // the first to join plays white, and is told their seat once black joins
+    let mut white = Process::client(&["--join", &address]);
+    white.wait_for_message(|message| matches!(message, Message::Notice(_)));
+    let mut black = Process::client(&["--join", &address]);
+    let seat = match white.wait_for_message(|message| matches!(message, Message::Seat { .. })) {
+        Message::Seat { game, token, .. } => format!("{}:{}", game, token),
+        _ => unreachable!(),
+    };
+    black.wait_for_message(|message| matches!(message, Message::Welcome { .. }));
+
+    white.type_line("move e2 e4");
+    assert_eq!(white.wait_for_message(is_move).to_string(), "move e2 e4");
+    assert_eq!(black.wait_for_message(is_move).to_string(), "move e2 e4");
+    black.type_line("move e7 e5");
+    assert_eq!(white.wait_for_message(is_move).to_string(), "move e7 e5");
+    let white_left = white_time(&mut white);
+
+    white.kill();
+    black.wait_for_message(|message| {
+        matches!(message, Message::Notice(text) if text.starts_with("White lost the connection"))
+    });
+    // white's clock is stopped while they are away
+    thread::sleep(Duration::from_secs(1));
+
+    let mut white = Process::client(&["--join", &address, "--resume", &seat]);
+    match white.wait_for_message(|message| matches!(message, Message::Welcome { .. })) {
+        Message::Welcome { side, game, .. } => {
+            assert_eq!(side, Some(Side::White));
+            assert!(game.ends_with("moves e2e4 e7e5"), "{}", game);
+        }
+        _ => unreachable!(),
+    }
+    let white_back = white_time(&mut white);
+    assert!(white_back <= white_left);
+    assert!(
+        white_left - white_back < Duration::from_millis(500),
+        "{:?} left before, {:?} after",
+        white_left,
+        white_back
+    );
+
+    white.type_line("move g1 f3");
+    assert_eq!(black.wait_for_message(is_move).to_string(), "move g1 f3");
+}
+
+#[test]
+fn a_hosted_game_is_played_between_two_processes() {
+    let mut host = Process::client(&["--host", "0", "--variant", "standard"]);
+    let address = host.address();
+    let mut guest = Process::client(&["--join", &address]);
+    match guest.wait_for_message(|message| matches!(message, Message::Welcome { .. })) {
+        Message::Welcome { side, .. } => assert_eq!(side, Some(Side::Black)),
+        _ => unreachable!(),
+    }
+
+    host.type_line("move e2 e4");
+    assert_eq!(host.wait_for_message(is_move).to_string(), "move e2 e4");
+    assert_eq!(guest.wait_for_message(is_move).to_string(), "move e2 e4");
+
+    // the host only plays legal moves, and only for the guest's side
+    guest.type_line("move e7 e4");
+    guest.wait_for_message(|message| matches!(message, Message::Reject(_)));
+    guest.type_line("resign white");
+    guest.wait_for_message(|message| matches!(message, Message::Reject(_)));
+    guest.type_line("move e7 e5");
+    assert_eq!(host.wait_for_message(is_move).to_string(), "move e7 e5");
+    assert_eq!(guest.wait_for_message(is_move).to_string(), "move e7 e5");
+
+    // nor does it play anything for the host that is not legal
+    host.type_line("move e2 e4");
+    host.wait_for_message(|message| matches!(message, Message::Reject(_)));
+    host.type_line("move g1 f3");
+    assert_eq!(guest.wait_for_message(is_move).to_string(), "move g1 f3");
+
+    guest.type_line("resign black");
+    assert_eq!(
+        host.wait_for_message(|message| matches!(message, Message::Resign(_))),
+        Message::Resign(Side::Black)
+    );
+}
@@ -0,0 +1,310 @@
+use std::thread;
+use std::time::Duration;
+
+use chess::{
+    net::{Connection, Message, Server},
+    rules::{Side, TimeControl, Variant},
+};
+
+mod common;
+
+use common::{net_move, TIMEOUT};
+
+/// Starts a server on a free port and returns its address
+fn start_server() -> String {
+    start_timed_server(None)
+}
+
+fn start_timed_server(time_control: Option<TimeControl>) -> String {
+    run(Server::bind("127.0.0.1:0", Variant::Standard, time_control).unwrap())
+}
+
+/// A server that does not wait long for players who lose the connection
+fn start_impatient_server() -> String {
+    let server = Server::bind("127.0.0.1:0", Variant::Standard, None).unwrap();
+    run(server.with_grace_period(Duration::from_millis(200)))
+}
+
+fn run(mut server: Server) -> String {
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    address
+}
+
+fn join(address: &str) -> Connection {
+    let mut connection = Connection::connect(address).unwrap();
+    connection.send(&Message::Play).unwrap();
+    connection
+}
+
+/// The next message that is not just for reading, about the clock or a seat
+fn next(connection: &mut Connection) -> Message {
+    loop {
+        match connection.recv(TIMEOUT).unwrap() {
+            Message::Notice(_) | Message::Clock { .. } | Message::Seat { .. } => (),
+            message => return message,
+        }
+    }
+}
+
+fn welcomed_side(connection: &mut Connection) -> Option<Side> {
+    match next(connection) {
+        Message::Welcome { side, variant, .. } => {
+            assert_eq!(variant, Variant::Standard);
+            side
+        }
+        message => panic!("Expected a welcome, got {}", message),
+    }
+}
+
+/// Reads up to the seat the server gives a player, and returns the message that
+/// takes it back
+fn resume_message(connection: &mut Connection) -> Message {
+    loop {
+        match connection.recv(TIMEOUT).unwrap() {
+            Message::Seat { game, token, .. } => return Message::Resume { game, token },
+            Message::Notice(_) => (),
+            message => panic!("Expected a seat, got {}", message),
+        }
+    }
+}
+
+/// Two players in a new game, white first
+fn start_game(address: &str) -> (Connection, Connection) {
+    let mut white = join(address);
+    // make sure the first player is waiting before the second one asks
+    assert!(matches!(white.recv(TIMEOUT), Ok(Message::Notice(_))));
+    let mut black = join(address);
+
+    assert_eq!(welcomed_side(&mut white), Some(Side::White));
+    assert_eq!(welcomed_side(&mut black), Some(Side::Black));
+    (white, black)
+}
+
+#[test]
+fn moves_are_checked_and_sent_to_both_players() {
+    let address = start_server();
+    let (mut white, mut black) = start_game(&address);
+
+    // out of turn
+    black.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert!(matches!(next(&mut black), Message::Reject(_)));
+
+    // not a legal move
+    white.send(&Message::Move(net_move("e2", "e5"))).unwrap();
+    assert!(matches!(next(&mut white), Message::Reject(_)));
+
+    white.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert_eq!(next(&mut white), Message::Move(net_move("e2", "e4")));
+    assert_eq!(next(&mut black), Message::Move(net_move("e2", "e4")));
+
+    black.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert_eq!(next(&mut white), Message::Move(net_move("e7", "e5")));
+    assert_eq!(next(&mut black), Message::Move(net_move("e7", "e5")));
+}
+
+#[test]
+fn many_games_are_played_at_once() {
+    let address = start_server();
+    let (mut white1, mut black1) = start_game(&address);
+    let (mut white2, mut black2) = start_game(&address);
+
+    white2.send(&Message::Move(net_move("d2", "d4"))).unwrap();
+    assert_eq!(next(&mut black2), Message::Move(net_move("d2", "d4")));
+
+    // the first game has not moved, so white can still play e4 there
+    white1.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert_eq!(next(&mut black1), Message::Move(net_move("e2", "e4")));
+    assert_eq!(next(&mut white1), Message::Move(net_move("e2", "e4")));
+    assert_eq!(next(&mut white2), Message::Move(net_move("d2", "d4")));
+}
+
+#[test]
+fn resigning_or_leaving_ends_the_game() {
+    let address = start_impatient_server();
+
+    let (mut white, mut black) = start_game(&address);
+    black.send(&Message::Resign(Side::White)).unwrap();
+    assert!(matches!(next(&mut black), Message::Reject(_)));
+    black.send(&Message::Resign(Side::Black)).unwrap();
+    assert_eq!(next(&mut white), Message::Resign(Side::Black));
+
+    // a player who does not come back in time loses
+    let (mut white, black) = start_game(&address);
+    drop(black);
+    assert_eq!(next(&mut white), Message::Resign(Side::Black));
+
+    white.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert!(matches!(next(&mut white), Message::Reject(_)));
+}
+
+#[test]
+fn spectators_get_the_game_so_far_and_every_move_after() {
+    let address = start_server();
+    let (mut white, mut black) = start_game(&address);
+
+    white.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert_eq!(next(&mut black), Message::Move(net_move("e2", "e4")));
+
+    let mut spectator = Connection::connect(&address).unwrap();
+    spectator.send(&Message::Watch(None)).unwrap();
+    match next(&mut spectator) {
+        Message::Welcome { side, game, .. } => {
+            assert_eq!(side, None);
+            assert!(game.ends_with("moves e2e4"), "{}", game);
+        }
+        message => panic!("Expected a welcome, got {}", message),
+    }
+
+    black.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert_eq!(next(&mut spectator), Message::Move(net_move("e7", "e5")));
+
+    // watching does not let you play
+    spectator
+        .send(&Message::Move(net_move("g1", "f3")))
+        .unwrap();
+    assert!(matches!(next(&mut spectator), Message::Reject(_)));
+
+    black.send(&Message::Resign(Side::Black)).unwrap();
+    assert_eq!(next(&mut spectator), Message::Resign(Side::Black));
+
+    let mut lost = Connection::connect(&address).unwrap();
+    lost.send(&Message::Watch(Some(99))).unwrap();
+    assert!(matches!(next(&mut lost), Message::Reject(_)));
+}
+
+#[test]
+fn anyone_who_joins_after_the_end_hears_how_it_ended() {
+    let address = start_server();
+    let mut white = join(&address);
+    assert!(matches!(white.recv(TIMEOUT), Ok(Message::Notice(_))));
+    let mut black = join(&address);
+    let resume = resume_message(&mut white);
+    assert_eq!(welcomed_side(&mut white), Some(Side::White));
+    assert_eq!(welcomed_side(&mut black), Some(Side::Black));
+
+    white.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert_eq!(next(&mut black), Message::Move(net_move("e2", "e4")));
+    assert_eq!(next(&mut white), Message::Move(net_move("e2", "e4")));
+    black.send(&Message::Resign(Side::Black)).unwrap();
+    assert_eq!(next(&mut white), Message::Resign(Side::Black));
+
+    let mut spectator = Connection::connect(&address).unwrap();
+    spectator.send(&Message::Watch(None)).unwrap();
+    assert_eq!(welcomed_side(&mut spectator), None);
+    assert_eq!(next(&mut spectator), Message::Resign(Side::Black));
+
+    // the game is kept while black is still there
+    drop(white);
+    thread::sleep(Duration::from_millis(100));
+    let mut white = Connection::connect(&address).unwrap();
+    white.send(&resume).unwrap();
+    assert_eq!(welcomed_side(&mut white), Some(Side::White));
+    assert_eq!(next(&mut white), Message::Resign(Side::Black));
+}
+
+#[test]
+fn connections_that_keep_sending_the_wrong_thing_are_dropped() {
+    let address = start_server();
+    let mut stranger = Connection::connect(&address).unwrap();
+
+    stranger.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert!(matches!(next(&mut stranger), Message::Reject(_)));
+    stranger.send(&Message::Watch(Some(99))).unwrap();
+    assert!(matches!(next(&mut stranger), Message::Reject(_)));
+
+    let token = "0123456789abcdef".to_owned();
+    stranger.send(&Message::Resume { game: 99, token }).unwrap();
+    assert!(matches!(next(&mut stranger), Message::Reject(_)));
+    assert!(stranger.recv(TIMEOUT).is_err());
+    assert!(!stranger.is_open());
+}
+
+#[test]
+fn the_server_keeps_the_clock() {
+    let address = start_timed_server(TimeControl::parse("1+2").ok());
+    let (mut white, mut black) = start_game(&address);
+
+    white.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert_eq!(next(&mut black), Message::Move(net_move("e2", "e4")));
+
+    // the clock only starts after the first move, which still earns its increment
+    let clock = |connection: &mut Connection| match connection.recv(TIMEOUT).unwrap() {
+        Message::Clock {
+            control,
+            white,
+            black,
+            ..
+        } => {
+            assert_eq!(control, TimeControl::parse("1+2").ok());
+            (white, black)
+        }
+        message => panic!("Expected the clock, got {}", message),
+    };
+    assert_eq!(
+        clock(&mut black),
+        (Duration::from_secs(62), Duration::from_secs(60))
+    );
+
+    // black's reply gets the increment for the time it took
+    black.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert_eq!(next(&mut black), Message::Move(net_move("e7", "e5")));
+    let (white_left, black_left) = clock(&mut black);
+    assert_eq!(white_left, Duration::from_secs(62));
+    assert!(black_left > Duration::from_secs(61) && black_left <= Duration::from_secs(62));
+}
+
+#[test]
+fn a_player_who_loses_the_connection_can_take_their_seat_back() {
+    let address = start_timed_server(TimeControl::parse("1+0").ok());
+    let mut white = join(&address);
+    assert!(matches!(white.recv(TIMEOUT), Ok(Message::Notice(_))));
+    let mut black = join(&address);
+    let resume = resume_message(&mut black);
+    assert_eq!(welcomed_side(&mut white), Some(Side::White));
+    assert_eq!(welcomed_side(&mut black), Some(Side::Black));
+
+    white.send(&Message::Move(net_move("e2", "e4"))).unwrap();
+    assert_eq!(next(&mut white), Message::Move(net_move("e2", "e4")));
+    assert_eq!(next(&mut black), Message::Move(net_move("e2", "e4")));
+
+    // black's clock stops while it is away, and the game goes on when it is back
+    drop(black);
+    loop {
+        match white.recv(TIMEOUT).unwrap() {
+            Message::Notice(text) if text.contains("lost the connection") => break,
+            Message::Notice(_) | Message::Clock { .. } => (),
+            message => panic!("Expected black to be away, got {}", message),
+        }
+    }
+    match white.recv(TIMEOUT).unwrap() {
+        Message::Clock { running, .. } => assert_eq!(running, None),
+        message => panic!("Expected the clock to stop, got {}", message),
+    }
+    thread::sleep(Duration::from_secs(1));
+
+    let mut black = Connection::connect(&address).unwrap();
+    let Message::Resume { game, .. } = resume else {
+        unreachable!();
+    };
+    let token = "0123456789abcdef".to_owned();
+    black.send(&Message::Resume { game, token }).unwrap();
+    assert!(matches!(next(&mut black), Message::Reject(_)));
+
+    black.send(&resume).unwrap();
+    match next(&mut black) {
+        Message::Welcome { side, game, .. } => {
+            assert_eq!(side, Some(Side::Black));
+            assert!(game.ends_with("moves e2e4"), "{}", game);
+        }
+        message => panic!("Expected a welcome, got {}", message),
+    }
+    match black.recv(TIMEOUT).unwrap() {
+        Message::Clock { black, .. } => assert!(black > Duration::from_millis(59_500)),
+        message => panic!("Expected the clock, got {}", message),
+    }
+
+    black.send(&Message::Move(net_move("e7", "e5"))).unwrap();
+    assert_eq!(next(&mut white), Message::Move(net_move("e7", "e5")));
+    assert_eq!(next(&mut black), Message::Move(net_move("e7", "e5")));
+}
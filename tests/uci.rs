@@ -0,0 +1,76 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use chess::rules::{BChange, Position, Variant};
+
+mod common;
+
+use common::square;
+
+#[test]
+fn every_move_round_trips() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    for variant in [Variant::Standard, Variant::RookPush] {
+        let position = Position::from_fen(fen).unwrap().with_variant(variant);
+
+        for change in position.get_all_possible_moves() {
+            assert_eq!(position.parse_uci_move(&change.to_uci()), Ok(change));
+        }
+    }
+}
+
+#[test]
+fn castling_is_a_king_move() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    let change = position.parse_uci_move("e1g1").unwrap();
+
+    assert_eq!(
+        change,
+        BChange::BothMove {
+            start1: square("e1"),
+            start2: square("h1"),
+            end1: square("g1"),
+            end2: square("f1"),
+        }
+    );
+}
+
+#[test]
+fn promotions_and_pushes() {
+    let position = Position::from_fen("4k3/P7/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+
+    assert!(matches!(
+        position.parse_uci_move("a7a8n"),
+        Ok(BChange::Promotion { .. })
+    ));
+    assert!(matches!(
+        position.parse_uci_move("a1a7q"),
+        Ok(BChange::PushPremote { .. })
+    ));
+    assert!(matches!(
+        position.parse_uci_move("a1e1"),
+        Ok(BChange::BothMove { .. })
+    ));
+    assert!(position.parse_uci_move("a7a8k").is_err());
+}
+
+#[test]
+fn the_engine_answers_even_if_stdin_closes_without_quit() {
+    let mut engine = Command::new(env!("CARGO_BIN_EXE_uci"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = engine.stdin.take().unwrap();
+    writeln!(stdin, "position startpos\ngo depth 6").unwrap();
+    drop(stdin);
+
+    let output = engine.wait_with_output().unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        output.lines().any(|line| line.starts_with("bestmove ")),
+        "{}",
+        output
+    );
+}
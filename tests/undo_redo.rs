@@ -0,0 +1,87 @@
+use chess::rules::{BoardPos, PieceDiff, PieceType, Position, Side};
+
+mod common;
+
+use common::play;
+
+#[test]
+fn redo_plays_undone_moves_again() {
+    let mut position = Position::new();
+    play(&mut position, &["e2e4", "e7e5", "g1f3"]);
+    let fen = position.to_fen();
+
+    assert!(position.undo().is_some());
+    assert!(position.undo().is_some());
+    assert_eq!(position.history().len(), 1);
+
+    assert!(position.redo().is_some());
+    assert!(position.redo().is_some());
+    assert!(position.redo().is_none());
+
+    assert_eq!(position.to_fen(), fen);
+    let sans: Vec<&str> = position.history().iter().map(|r| r.san.as_str()).collect();
+    assert_eq!(sans, ["e4", "e5", "Nf3"]);
+}
+
+#[test]
+fn a_new_move_clears_the_redo_stack() {
+    let mut position = Position::new();
+    play(&mut position, &["e2e4", "e7e5"]);
+
+    position.undo();
+    assert!(position.can_redo());
+
+    play(&mut position, &["c7c5"]);
+    assert!(!position.can_redo());
+    assert!(position.redo().is_none());
+}
+
+#[test]
+fn searching_does_not_touch_the_redo_stack() {
+    let mut position = Position::new();
+    play(&mut position, &["e2e4", "e7e5"]);
+    position.undo();
+
+    position.perft(2);
+    assert!(position.can_redo());
+}
+
+#[test]
+fn undo_keeps_en_passant_available() {
+    let mut position = Position::new();
+    play(&mut position, &["e2e4", "a7a6", "e4e5", "d7d5"]);
+    let fen = position.to_fen();
+
+    play(&mut position, &["g1f3"]);
+    assert!(position.undo().is_some());
+
+    assert_eq!(position.to_fen(), fen);
+    assert!(position.parse_uci_move("e5d6").is_ok());
+}
+
+#[test]
+fn undoing_a_capturing_promotion_brings_both_pieces_back() {
+    let mut position = Position::from_fen("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let fen = position.to_fen();
+    play(&mut position, &["b7a8q"]);
+
+    let a8 = BoardPos::from_algebraic("a8").unwrap();
+    let b7 = BoardPos::from_algebraic("b7").unwrap();
+    assert_eq!(
+        position.undo().unwrap(),
+        [
+            PieceDiff::Promoted {
+                at: a8,
+                piece_type: PieceType::Pawn,
+                side: Side::White,
+            },
+            PieceDiff::Moved { from: a8, to: b7 },
+            PieceDiff::Added {
+                at: a8,
+                piece_type: PieceType::Rook,
+                side: Side::Black,
+            },
+        ]
+    );
+    assert_eq!(position.to_fen(), fen);
+}